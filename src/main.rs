@@ -1,4 +1,8 @@
-use std::{f32::consts::PI, time::Duration};
+use std::{
+    f32::consts::{PI, TAU},
+    sync::{OnceLock, RwLock},
+    time::Duration,
+};
 
 use bevy::{
     app::AppExit, asset::LoadState, audio::VolumeLevel, prelude::*, utils::HashMap,
@@ -20,18 +24,26 @@ fn main() {
         .add_systems(Update, check_loading.run_if(in_state(AppState::Loading)))
         .add_systems(OnExit(AppState::Loading), gen_atlas)
         .add_systems(OnExit(AppState::Loading), play_song)
+        .add_systems(
+            OnEnter(AppState::MainMenu),
+            (reset_game, ui_main_menu).chain(),
+        )
+        .add_systems(OnExit(AppState::MainMenu), despawn_main_menu)
+        .add_systems(Update, menu_buttons.run_if(in_state(AppState::MainMenu)))
         .add_systems(
             OnEnter(AppState::Setup),
             (setup_scene, setup_ui_topleft).chain(),
         )
-        .add_systems(Update, escape_exit)
+        .add_systems(Update, (escape_exit, apply_ui_scale, ui_toasts))
         .add_systems(
             Update,
             (
                 turn,
+                follow_orbits,
                 ship_orbit,
                 ship_plan,
                 on_build_construction,
+                on_upgrade_construction,
                 on_destroy_construction,
                 on_modify_resource,
             )
@@ -39,6 +51,35 @@ fn main() {
                 .run_if(in_state(AppState::Gameplay)),
         )
         .add_systems(Update, send_end_turn.run_if(in_state(AppState::Gameplay)))
+        .add_systems(
+            Update,
+            (
+                stats_hotkey,
+                speed_hotkey,
+                ui_stats,
+                research_hotkey,
+                ui_research,
+                orbits_hotkey,
+                ui_orbits,
+                draw_lanes,
+            )
+                .run_if(in_state(AppState::Gameplay)),
+        )
+        .add_systems(Update, tutorial.run_if(in_state(AppState::Gameplay)))
+        .add_systems(
+            Update,
+            check_achievements
+                .after(turn)
+                .run_if(in_state(AppState::Gameplay)),
+        )
+        .add_systems(
+            Update,
+            navigation
+                .before(highlight)
+                .before(button_system)
+                .before(tooltip)
+                .run_if(in_state(AppState::Gameplay)),
+        )
         .add_systems(
             Update,
             play_autoactions.run_if(in_state(AppState::Gameplay)),
@@ -49,11 +90,21 @@ fn main() {
                 highlight,
                 ui_on_node_selected_constr,
                 ui_on_node_selected_move,
-                ui_on_node_selected_planet,
                 ui_on_construction,
                 ui_topleft,
                 move_hotkeys,
                 button_system,
+                tooltip,
+                ui_hud,
+                ui_shuttle_menu,
+                ui_hud_shuttles,
+                draw_shuttles,
+                ui_scout_planet,
+                fog_of_war,
+                ui_hud_encounters,
+                ui_encounter,
+                draw_encounters,
+                ui_objectives,
             )
                 .run_if(in_state(AppState::Gameplay)),
         )
@@ -62,21 +113,367 @@ fn main() {
             (interpolation_fx, on_modify_resource_fx).run_if(in_state(AppState::Gameplay)),
         )
         .add_systems(OnEnter(AppState::GameOver), ui_gameover)
-        .add_systems(OnEnter(AppState::GameWon), ui_win)
+        .add_systems(
+            OnEnter(AppState::GameWon),
+            (win_achievements, campaign_win, ui_win),
+        )
+        .add_systems(
+            Update,
+            gameover_reset
+                .run_if(in_state(AppState::GameOver).or_else(in_state(AppState::GameWon))),
+        )
         .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
         .insert_resource(AssetHandles::default())
         .insert_resource(Map::test())
         .insert_resource(AutoActions::default())
         .insert_resource(TurnCount::default())
+        .insert_resource(Hover::default())
+        .insert_resource(ShipNet::default())
+        .insert_resource(Stats::default())
+        .insert_resource(Tutorial::default())
+        .insert_resource(Theme::default())
+        .insert_resource(Profile::load())
+        .insert_resource(Leaderboard::load())
+        .insert_resource(Campaign::load())
+        .insert_resource(AnimSpeed::default())
+        .insert_resource(NavCursor::default())
+        .insert_resource(Crew::default())
+        .insert_resource(Research::default())
+        .insert_resource(PowerGrid::default())
+        .insert_resource(Shuttles::default())
+        .insert_resource(Encounters::default())
+        .insert_resource(Scenario::default())
         .add_event::<EndTurn>()
         .add_event::<BuildConstruction>()
+        .add_event::<UpgradeConstruction>()
         .add_event::<DestroyConstruction>()
         .add_event::<ModifyResource>()
         .add_event::<ModifyResourceFx>()
         .add_event::<UiEvent>()
+        .add_event::<Toast>()
         .run();
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+enum Language {
+    #[default]
+    English,
+    Italian,
+}
+
+impl Language {
+    fn iter() -> impl Iterator<Item = Self> {
+        [Self::English, Self::Italian].iter().cloned()
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Self::English => include_str!("../assets/lang/en.txt"),
+            Self::Italian => include_str!("../assets/lang/it.txt"),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Italian => "Italiano",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            Self::English => Self::Italian,
+            Self::Italian => Self::English,
+        }
+    }
+}
+
+static LANGUAGE: RwLock<Language> = RwLock::new(Language::English);
+static STRINGS: OnceLock<HashMap<Language, HashMap<&'static str, String>>> = OnceLock::new();
+
+fn parse_strings(source: &'static str) -> HashMap<&'static str, String> {
+    source
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim().replace("\\n", "\n")))
+        .collect()
+}
+
+fn strings() -> &'static HashMap<Language, HashMap<&'static str, String>> {
+    STRINGS.get_or_init(|| {
+        let tables: HashMap<Language, HashMap<&'static str, String>> = Language::iter()
+            .map(|lang| (lang, parse_strings(lang.source())))
+            .collect();
+        let english = tables.get(&Language::English).unwrap();
+        for (lang, table) in tables.iter() {
+            for key in english.keys().filter(|key| !table.contains_key(*key)) {
+                warn!("missing string {} for {:?}, using English", key, lang);
+            }
+        }
+        tables
+    })
+}
+
+fn language() -> Language {
+    *LANGUAGE.read().unwrap()
+}
+
+fn set_language(lang: Language) {
+    *LANGUAGE.write().unwrap() = lang;
+}
+
+/// translated string for the message id
+fn tr(key: &str) -> String {
+    let strings = strings();
+    if let Some(value) = strings.get(&language()).and_then(|t| t.get(key)) {
+        return value.clone();
+    }
+    if let Some(value) = strings.get(&Language::English).and_then(|t| t.get(key)) {
+        return value.clone();
+    }
+    warn!("missing string {}", key);
+    key.to_string()
+}
+
+/// translated string with every `{}` replaced by the arguments in order
+fn trf(key: &str, args: &[&dyn std::fmt::Display]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let template = tr(key);
+    let mut parts = template.split("{}").peekable();
+    while let Some(part) = parts.next() {
+        out += part;
+        if parts.peek().is_some() {
+            if let Some(arg) = args.next() {
+                out += &arg.to_string();
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Palette {
+    #[default]
+    Classic,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl Palette {
+    fn next(&self) -> Self {
+        match self {
+            Self::Classic => Self::Deuteranopia,
+            Self::Deuteranopia => Self::Tritanopia,
+            Self::Tritanopia => Self::Classic,
+        }
+    }
+}
+
+impl std::fmt::Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Classic => tr("palette.classic"),
+            Self::Deuteranopia => tr("palette.deuteranopia"),
+            Self::Tritanopia => tr("palette.tritanopia"),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// colours and sizes shared by every ui builder
+#[derive(Resource, Debug, Clone)]
+struct Theme {
+    palette: Palette,
+    scale: f32,
+    text: Color,
+    panel: Color,
+    border: Color,
+    button: Color,
+    button_border: Color,
+    button_hovered: Color,
+    button_pressed: Color,
+    muted: Color,
+    /// labels over the light sprites on the map
+    world_text: Color,
+    positive: Color,
+    negative: Color,
+    focus: Color,
+    resources: [Color; 9],
+    series: Vec<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(Palette::default(), 1.)
+    }
+}
+
+impl Theme {
+    fn new(palette: Palette, scale: f32) -> Self {
+        // the colorblind palettes are picked from Okabe-Ito
+        let orange = Color::rgb_u8(230, 159, 0);
+        let sky = Color::rgb_u8(86, 180, 233);
+        let green = Color::rgb_u8(0, 158, 115);
+        let yellow = Color::rgb_u8(240, 228, 66);
+        let blue = Color::rgb_u8(0, 114, 178);
+        let vermillion = Color::rgb_u8(213, 94, 0);
+        let purple = Color::rgb_u8(204, 121, 167);
+        let (positive, negative, focus, resources, series) = match palette {
+            Palette::Classic => (
+                Color::GREEN,
+                Color::RED,
+                Color::YELLOW,
+                [
+                    Color::YELLOW,
+                    Color::RED,
+                    Color::GREEN,
+                    Color::GRAY,
+                    Color::BLUE,
+                    Color::PINK,
+                    Color::PURPLE,
+                    Color::CYAN,
+                    Color::ORANGE,
+                ],
+                vec![
+                    Color::YELLOW,
+                    Color::PURPLE,
+                    Color::ORANGE,
+                    Color::GREEN,
+                    Color::CYAN,
+                    Color::GRAY,
+                    Color::RED,
+                    Color::PINK,
+                    Color::WHITE,
+                    Color::TEAL,
+                    Color::SILVER,
+                ],
+            ),
+            Palette::Deuteranopia => (
+                blue,
+                orange,
+                yellow,
+                [
+                    yellow,
+                    vermillion,
+                    green,
+                    Color::GRAY,
+                    sky,
+                    purple,
+                    orange,
+                    blue,
+                    Color::WHITE,
+                ],
+                vec![
+                    yellow,
+                    purple,
+                    orange,
+                    green,
+                    sky,
+                    Color::GRAY,
+                    blue,
+                    vermillion,
+                    Color::WHITE,
+                    Color::DARK_GRAY,
+                    Color::SILVER,
+                ],
+            ),
+            Palette::Tritanopia => (
+                green,
+                vermillion,
+                purple,
+                [
+                    purple,
+                    vermillion,
+                    green,
+                    Color::GRAY,
+                    Color::WHITE,
+                    sky,
+                    orange,
+                    blue,
+                    yellow,
+                ],
+                vec![
+                    purple,
+                    vermillion,
+                    green,
+                    Color::WHITE,
+                    Color::GRAY,
+                    sky,
+                    orange,
+                    yellow,
+                    blue,
+                    Color::DARK_GRAY,
+                    Color::SILVER,
+                ],
+            ),
+        };
+        Self {
+            palette,
+            scale,
+            text: Color::WHITE,
+            panel: Color::rgb(0.1, 0.1, 0.1),
+            border: Color::WHITE,
+            button: Color::rgb(0.14, 0.14, 0.14),
+            button_border: Color::rgb(0.2, 0.2, 0.2),
+            button_hovered: Color::BLACK.with_a(0.3),
+            button_pressed: negative,
+            muted: Color::rgb(0.2, 0.2, 0.2),
+            world_text: Color::BLACK,
+            positive,
+            negative,
+            focus,
+            resources,
+            series,
+        }
+    }
+
+    fn text_style(&self, handles: &AssetHandles, font_size: f32) -> TextStyle {
+        TextStyle {
+            font: handles.font.clone(),
+            font_size,
+            color: self.text,
+        }
+    }
+
+    fn huge(&self, handles: &AssetHandles) -> TextStyle {
+        self.text_style(handles, 40.0)
+    }
+
+    fn big(&self, handles: &AssetHandles) -> TextStyle {
+        self.text_style(handles, 30.0)
+    }
+
+    fn normal(&self, handles: &AssetHandles) -> TextStyle {
+        self.text_style(handles, 20.0)
+    }
+
+    fn small(&self, handles: &AssetHandles) -> TextStyle {
+        self.text_style(handles, 13.0)
+    }
+
+    /// text drawn on the map, UiScale doesn't reach it
+    fn world(&self, style: TextStyle) -> TextStyle {
+        TextStyle {
+            font_size: style.font_size * self.scale,
+            color: self.world_text,
+            ..style
+        }
+    }
+
+    fn resource_color(&self, var: &ResourceVariant) -> Color {
+        let i = ResourceVariant::iter().position(|v| v == *var).unwrap_or(0);
+        self.resources[i]
+    }
+}
+
+fn apply_ui_scale(theme: Res<Theme>, mut ui_scale: ResMut<UiScale>) {
+    if theme.is_changed() {
+        ui_scale.scale = theme.scale as f64;
+    }
+}
+
 fn escape_exit(keys: Res<Input<KeyCode>>, mut exit: EventWriter<AppExit>) {
     if keys.pressed(KeyCode::Escape) {
         exit.send(AppExit);
@@ -87,6 +484,7 @@ fn escape_exit(keys: Res<Input<KeyCode>>, mut exit: EventWriter<AppExit>) {
 enum AppState {
     #[default]
     Loading,
+    MainMenu,
     Setup,
     Gameplay,
     GameOver,
@@ -139,7 +537,7 @@ fn check_loading(
         LoadState::Loaded
     );
     if loaded {
-        next_state.set(AppState::Setup);
+        next_state.set(AppState::MainMenu);
     }
 }
 
@@ -170,11 +568,83 @@ fn gen_atlas(mut texture_atlases: ResMut<Assets<TextureAtlas>>, mut handles: Res
 /// Map
 #[derive(Clone, Debug, Resource)]
 struct Map {
+    /// scores are kept per map
+    name: String,
+    /// sunlight compared to the usual star
+    brightness: f32,
     groups: HashMap<GroupId, Vec<NodeId>>,
     edges: Vec<(GroupId, GroupId)>,
     positions: HashMap<NodeId, Vec2>,
     group_positions: HashMap<GroupId, Vec2>,
     occupation: HashMap<NodeId, NodeOccupant>,
+    /// buildings that produced during the last turn
+    fired: Vec<NodeId>,
+    /// transfer lanes drawn on the map, they become edges while their window is open
+    lanes: Vec<(GroupId, GroupId)>,
+    orbits: HashMap<GroupId, Orbit>,
+    /// turn the planets are at
+    turn: u32,
+    kinds: HashMap<GroupId, BodyKind>,
+    /// groups visited by the ship or a probe, the others are hidden
+    scouted: Vec<GroupId>,
+    deposits: HashMap<GroupId, HashMap<ResourceVariant, Deposit>>,
+}
+
+/// natural reserves of a resource in a group
+#[derive(Debug, Clone)]
+struct Deposit {
+    left: u32,
+    initial: u32,
+}
+
+impl Deposit {
+    fn new(amt: u32) -> Self {
+        Self {
+            left: amt,
+            initial: amt,
+        }
+    }
+
+    /// output falls with what is left and stops when the deposit runs out
+    fn extract(&mut self, wanted: u32) -> u32 {
+        if self.initial == 0 {
+            return 0;
+        }
+        // rounded up so that the last bits can be dug too
+        let amt = (wanted as u64 * self.left as u64).div_ceil(self.initial as u64) as u32;
+        let amt = amt.min(self.left);
+        self.left -= amt;
+        amt
+    }
+}
+
+/// what a group is made of, known once scouted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BodyKind {
+    Asteroid,
+    Rocky,
+    GasGiant,
+}
+
+impl BodyKind {
+    fn sprite_scale(&self) -> f32 {
+        match self {
+            Self::Asteroid => 0.6,
+            Self::Rocky => 1.,
+            Self::GasGiant => 1.6,
+        }
+    }
+}
+
+impl std::fmt::Display for BodyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Asteroid => tr("body.asteroid"),
+            Self::Rocky => tr("body.rocky"),
+            Self::GasGiant => tr("body.gas_giant"),
+        };
+        write!(f, "{}", name)
+    }
 }
 
 #[derive(Debug, Clone, Deref, DerefMut, PartialEq, Eq, Hash)]
@@ -187,6 +657,8 @@ enum NodeOccupant {
     Construction {
         var: ConstructionVariant,
         cooldown: u32,
+        /// 1 when built, up to MAX_TIER with upgrades
+        tier: u32,
     },
     Stockpile {
         var: ResourceVariant,
@@ -196,1162 +668,3693 @@ enum NodeOccupant {
 
 const MAX_STOCKPILE: u32 = 100;
 
-impl Map {
-    fn test() -> Self {
-        let mut planets_pos = vec![
-            (GroupId(0), Vec2::new(0., -256.)),
-            (GroupId(1), Vec2::new(-200., 0.)),
-            (GroupId(2), Vec2::new(0., -60.)),
-            (GroupId(3), Vec2::new(160., -40.)),
-            (GroupId(4), Vec2::new(160., 160.)),
-            (GroupId(5), Vec2::new(-160., 240.)),
-            (GroupId(6), Vec2::new(-320., 80.)),
-            (GroupId(7), Vec2::new(-440., -20.)),
-        ];
-        let off = Vec2::new(-32., 16.);
-        for (_, pos) in planets_pos.iter_mut() {
-            *pos = *pos + off;
-        }
-        let w = 64.;
-        let s = 64.;
-        let wh = w / 2.;
-        let node_pos = vec![
-            //
-            planets_pos[0].1 + Vec2::new(-192., 0.),
-            planets_pos[0].1 + Vec2::new(-128., 0.),
-            planets_pos[0].1 + Vec2::new(-64., 0.),
-            planets_pos[0].1 + Vec2::new(0., 0.),
-            planets_pos[0].1 + Vec2::new(64., 0.),
-            //
-            planets_pos[1].1 + Vec2::new(0., -s),
-            planets_pos[1].1 + Vec2::new(0., -s - w),
-            planets_pos[1].1 + Vec2::new(w, -s - w / 2.),
-            //
-            planets_pos[2].1 + Vec2::new(-wh, s),
-            planets_pos[2].1 + Vec2::new(wh, s),
-            planets_pos[2].1 + Vec2::new(wh, s + w),
-            planets_pos[2].1 + Vec2::new(-wh, s + w),
-            //
-            planets_pos[3].1 + Vec2::new(s, wh),
-            planets_pos[3].1 + Vec2::new(s, -wh),
-            planets_pos[3].1 + Vec2::new(s + w, wh),
-            planets_pos[3].1 + Vec2::new(s + w, -wh),
-            //
-            planets_pos[4].1 + Vec2::new(-wh - w, s),
-            planets_pos[4].1 + Vec2::new(-wh, s),
-            planets_pos[4].1 + Vec2::new(wh, s),
-            planets_pos[4].1 + Vec2::new(wh + w, s),
-            planets_pos[4].1 + Vec2::new(-wh - w, s + w),
-            planets_pos[4].1 + Vec2::new(-wh, s + w),
-            planets_pos[4].1 + Vec2::new(wh, s + w),
-            planets_pos[4].1 + Vec2::new(wh + w, s + w),
-            //
-            planets_pos[5].1 + Vec2::new(-wh, -s),
-            planets_pos[5].1 + Vec2::new(wh, -s),
-            //
-            planets_pos[6].1 + Vec2::new(0., s),
-            planets_pos[6].1 + Vec2::new(0., s + w),
-            planets_pos[6].1 + Vec2::new(0., s + w + w),
-            //
-            planets_pos[7].1 + Vec2::new(-w, -s),
-            planets_pos[7].1 + Vec2::new(0., -s),
-            planets_pos[7].1 + Vec2::new(w, -s),
-            planets_pos[7].1 + Vec2::new(-w, -s - w),
-            planets_pos[7].1 + Vec2::new(0., -s - w),
-        ];
+const MAX_TIER: u32 = 3;
 
-        let mut map = Self {
-            groups: HashMap::from([
-                (GroupId(0), (0..5).map(|i| NodeId(i)).collect()),
-                (GroupId(1), (5..8).map(|i| NodeId(i)).collect()),
-                (GroupId(2), (8..12).map(|i| NodeId(i)).collect()),
-                (GroupId(3), (12..16).map(|i| NodeId(i)).collect()),
-                (GroupId(4), (16..24).map(|i| NodeId(i)).collect()),
-                (GroupId(5), (24..26).map(|i| NodeId(i)).collect()),
-                (GroupId(6), (26..29).map(|i| NodeId(i)).collect()),
-                (GroupId(7), (29..34).map(|i| NodeId(i)).collect()),
-            ]),
-            edges: vec![
-                (GroupId(0), GroupId(1)),
-                (GroupId(1), GroupId(2)),
-                (GroupId(1), GroupId(5)),
-                (GroupId(1), GroupId(6)),
-                (GroupId(2), GroupId(3)),
-                (GroupId(3), GroupId(4)),
-                (GroupId(4), GroupId(5)),
-                (GroupId(5), GroupId(6)),
-                (GroupId(6), GroupId(7)),
-            ],
-            positions: HashMap::from_iter(
-                node_pos
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, pos)| (NodeId(i), pos)),
-            ),
-            group_positions: HashMap::from_iter(planets_pos.into_iter()),
-            occupation: HashMap::default(),
-        };
-        map
+// a planet at 1 AU goes around the star in this many turns
+const ORBIT_TURNS: f32 = 4.;
+// lanes are open while their planets are less than this many degrees apart
+const TRANSFER_WINDOW: f32 = 100.;
+// each started distance of this many AU costs 1 fusion fuel
+const JUMP_AU: f32 = 3.;
+const TIMELINE_TURNS: u32 = 20;
+// where the star sits on the map
+const SUN: Vec2 = Vec2::new(-60., 50.);
+// pixels per AU, the orbital plane is seen at a slant so y is squashed
+const AU_SCREEN: Vec2 = Vec2::new(60., 20.);
+
+const PROBE_FUEL_PER_JUMP: u32 = 3;
+
+// solar fields give their base output at this distance from the star
+const SUNLIGHT_AU: f32 = 2.8;
+// no atmosphere or dust in the way of the ship's panels
+const SHIP_SUNLIGHT_BONUS: f32 = 5. / 3.;
+
+/// circular orbit around the star, radius in AU and starting angle in degrees
+#[derive(Debug, Clone)]
+struct Orbit {
+    radius: f32,
+    phase: f32,
+}
+
+impl Orbit {
+    /// kepler's third law
+    fn period(&self) -> f32 {
+        ORBIT_TURNS * self.radius.powf(1.5)
     }
 
-    fn star(&self, group_id: &GroupId) -> Vec<GroupId> {
-        self.edges
-            .iter()
-            .filter_map(|edge| match edge {
-                (n, m) if n == group_id => Some(m.clone()),
-                (m, n) if n == group_id => Some(m.clone()),
-                _ => None,
-            })
-            .collect()
+    fn angle(&self, turn: u32) -> f32 {
+        self.phase.to_radians() + TAU * turn as f32 / self.period()
     }
 
-    fn group_from_node(&self, id: &NodeId) -> GroupId {
-        self.groups
-            .iter()
-            .find(|(_, ids)| ids.contains(id))
-            .expect("no group")
-            .0
-            .clone()
+    fn position(&self, turn: u32) -> Vec2 {
+        Vec2::from_angle(self.angle(turn)) * self.radius
     }
 
-    fn set_at(&mut self, id: &NodeId, occ: NodeOccupant) {
-        self.occupation.insert(id.clone(), occ);
+    /// where the planet is drawn on the map
+    fn screen_position(&self, turn: u32) -> Vec2 {
+        SUN + self.position(turn) * AU_SCREEN
     }
+}
 
-    fn get_group_bunch(&self, id: &GroupId) -> Bunch {
-        let group = self.groups.get(id).expect("no group");
-        group
-            .iter()
-            .filter_map(|node_id| match self.occupation.get(node_id) {
-                Some(NodeOccupant::Stockpile { var, amt }) => {
-                    Some(Bunch::single(var.clone(), *amt))
-                }
-                _ => None,
-            })
-            .sum()
+const FOOD_PER_CREW: u32 = 1;
+
+/// crew of the ship, sleeping members don't eat but can't staff buildings
+#[derive(Resource, Debug, Clone)]
+struct Crew {
+    awake: u32,
+    asleep: u32,
+}
+
+impl Default for Crew {
+    fn default() -> Self {
+        Self {
+            awake: 2,
+            asleep: 4,
+        }
     }
+}
 
-    fn get_lowest_stockpile(&self, id: &GroupId, v: &ResourceVariant) -> NodeId {
-        #[cfg(feature = "dbtrace")]
-        println!("getting lowest stockpile for {:?} {:?}", id, v);
-        let group = self.groups.get(id).expect("no group");
-        let node_id = group
-            .iter()
-            .filter_map(|node_id| match self.occupation.get(node_id) {
-                Some(NodeOccupant::Stockpile { var, amt }) if v == var => Some((node_id, amt)),
-                _ => None,
-            })
-            .min_by(|a, b| a.1.cmp(&b.1))
-            .expect("no stockpile")
-            .0
-            .clone();
-        #[cfg(feature = "dbtrace")]
-        println!("found lowest stockpile at {:?}", node_id);
-        node_id
+impl Crew {
+    fn food_needed(&self) -> u32 {
+        self.awake * FOOD_PER_CREW
     }
 
-    /// add to highest stockpile until amt = 0 or put in empty.
-    fn add_resource_in_group(
-        &mut self,
-        group_id: &GroupId,
-        v: &ResourceVariant,
-        amt: u32,
-    ) -> Result<Vec<(NodeId, u32, i32)>, String> {
-        #[cfg(feature = "dbtrace")]
-        println!("adding to {:?} {:?} {:?}", group_id, v, amt);
-        assert!(amt <= 100);
-        let group = self.groups.get(group_id).expect("no group").clone();
-        let mut left = amt;
-        let mut actions = vec![];
-        for _i in 0..16 {
-            if left <= 0 {
-                break;
-            }
-            // is there already a pile?
-            if let Some(node_id) = group
-                .iter()
-                .filter_map(|node_id| match self.occupation.get(node_id) {
-                    Some(NodeOccupant::Stockpile { var, amt })
-                        if (v == var && *amt < MAX_STOCKPILE) =>
-                    {
-                        Some((node_id, *amt))
-                    }
-                    _ => None,
-                })
-                .max_by(|a, b| a.1.cmp(&b.1))
-            {
-                // insert into highest
-                let highest = self.occupation.get_mut(node_id.0).unwrap();
-                match highest {
-                    NodeOccupant::Stockpile { amt: stock_amt, .. } => {
-                        let clamped = left.min(MAX_STOCKPILE - *stock_amt);
-                        actions.push((node_id.0.clone(), clamped + *stock_amt, clamped as i32));
-                        *stock_amt += clamped;
-                        left -= clamped;
-                    }
-                    _ => unreachable!(),
-                }
-            } else {
-                // insert into an eventual empty tile
-                if let Some(empty_id) = group
-                    .iter()
-                    .find(|node_id| self.occupation.get(*node_id).is_none())
-                {
-                    actions.push((empty_id.clone(), left, left as i32));
-                    self.set_at(
-                        empty_id,
-                        NodeOccupant::Stockpile {
-                            var: v.clone(),
-                            amt: left,
-                        },
-                    )
-                }
-                left = 0;
-            };
+    fn wake(&mut self) {
+        if self.asleep > 0 {
+            self.asleep -= 1;
+            self.awake += 1;
         }
-        Ok(actions)
     }
-}
 
-// events
-#[derive(Event)]
-struct EndTurn;
+    /// at least one member stays awake to fly the ship
+    fn sleep(&mut self) {
+        if self.awake > 1 {
+            self.awake -= 1;
+            self.asleep += 1;
+        }
+    }
 
-#[derive(Event)]
-struct BuildConstruction {
-    node_id: NodeId,
-    var: ConstructionVariant,
-}
+    /// one member dies, a sleeper is woken if nobody is left to fly the ship
+    fn starve(&mut self) -> bool {
+        self.awake = self.awake.saturating_sub(1);
+        if self.awake > 0 || self.asleep == 0 {
+            return false;
+        }
+        self.wake();
+        true
+    }
 
-#[derive(Event)]
-struct DestroyConstruction {
-    node_id: NodeId,
+    /// the crew lives on the ship and only reaches the planet it orbits
+    fn can_staff(
+        staff: u32,
+        var: &ConstructionVariant,
+        group: &GroupId,
+        ship_at: &GroupId,
+    ) -> bool {
+        var.get_crew() == 0
+            || (var.get_crew() <= staff && (*group == GroupId(0) || group == ship_at))
+    }
 }
 
-#[derive(Event)]
-struct ModifyResource {
-    node_id: NodeId,
-    var: ResourceVariant,
-    abs: u32,
+#[derive(Debug, Clone)]
+enum Objective {
+    /// have this much on the ship
+    Stock(ResourceVariant, u32),
+    /// orbit the planet by the turn
+    Reach(GroupId, u32),
+    /// lost when no building of the kind is left on the planet
+    Keep(ConstructionVariant, GroupId),
+    /// lost when the ship ends a turn with less food
+    MinFood(u32),
+    /// lost when the turn comes and the game isn't won
+    TurnLimit(u32),
+    /// lost when nobody is awake to fly the ship
+    Crew,
 }
 
-#[derive(Event)]
-struct ModifyResourceFx {
-    from: NodeId,
-    to: NodeId,
-    var: ResourceVariant,
-    diff: i32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pending,
+    Done,
+    Failed,
 }
 
-#[derive(Event)]
-enum UiEvent {
-    SelectNodeForConstruction(NodeId),
-    ConstructOnNode(NodeId),
-    SelectNodeForMove(NodeId, bool),
-    SelectPlanet(GroupId),
-    GameOver,
-    Close,
+impl Objective {
+    /// stock and reach have to be done to win, the others only hold
+    fn is_goal(&self) -> bool {
+        matches!(self, Self::Stock(..) | Self::Reach(..))
+    }
+
+    /// `stock Food 100`, `reach 3 10`, `keep Lab 2`, `min_food 5`, `turn_limit 80` or `crew`
+    fn parse(text: &str) -> Option<Self> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let number = |i: usize| words.get(i)?.parse::<u32>().ok();
+        Some(match *words.first()? {
+            "stock" => Self::Stock(
+                variant_named(ResourceVariant::iter(), words.get(1)?)?,
+                number(2)?,
+            ),
+            "reach" => Self::Reach(GroupId(number(1)? as usize), number(2)?),
+            "keep" => Self::Keep(
+                variant_named(ConstructionVariant::iter(), words.get(1)?)?,
+                GroupId(number(2)? as usize),
+            ),
+            "min_food" => Self::MinFood(number(1)?),
+            "turn_limit" => Self::TurnLimit(number(1)?),
+            "crew" => Self::Crew,
+            _ => return None,
+        })
+    }
+
+    fn describe(&self, map: &Map) -> String {
+        let ship = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+        match self {
+            Self::Stock(var, amt) => trf(
+                "objective.stock",
+                &[amt, &var.to_string(), ship.res.get(var).unwrap_or(&0)],
+            ),
+            Self::Reach(group, turn) => trf("objective.reach", &[&group.0, turn]),
+            Self::Keep(var, group) => trf("objective.keep", &[&var.to_string(), &group.0]),
+            Self::MinFood(food) => trf("objective.min_food", &[food]),
+            Self::TurnLimit(turn) => trf("objective.turn_limit", &[turn]),
+            Self::Crew => tr("objective.crew"),
+        }
+    }
 }
 
-#[derive(Event)]
-struct NodeConstruct {
-    node_id: NodeId,
+const SYSTEMS: &[(&str, &str)] = &[
+    ("cradle", include_str!("../assets/systems/cradle.txt")),
+    ("kepler", include_str!("../assets/systems/kepler.txt")),
+    ("drift", include_str!("../assets/systems/drift.txt")),
+    ("haven", include_str!("../assets/systems/haven.txt")),
+];
+
+/// a campaign stop, they all use the same bodies with their own objectives and modifiers
+#[derive(Debug, Clone)]
+struct StarSystem {
+    id: &'static str,
+    /// message id of the name
+    name: String,
+    /// fraction of the star map screen
+    position: Vec2,
+    /// systems that can be jumped to once this one is won
+    next: Vec<String>,
+    /// percent of the usual sunlight and deposits
+    sunlight: u32,
+    reserves: u32,
+    /// degrees added to every orbit
+    phase: f32,
+    start: Bunch,
+    objectives: Vec<Objective>,
 }
 
-fn on_destroy_construction(
-    mut events: EventReader<DestroyConstruction>,
-    mut commands: Commands,
-    query: Query<(Entity, &NodeIdMarker)>,
-) {
-    for event in events.iter() {
-        for (e, marker) in query.iter() {
-            if marker.node_id == event.node_id {
-                commands.entity(e).despawn_recursive();
+impl StarSystem {
+    fn parse(id: &'static str, source: &str) -> Self {
+        let mut system = Self {
+            id,
+            name: id.to_string(),
+            position: Vec2::splat(0.5),
+            next: vec![],
+            sunlight: 100,
+            reserves: 100,
+            phase: 0.,
+            start: Bunch::default(),
+            objectives: vec![],
+        };
+        for line in source
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        {
+            let parsed = line.split_once('=').and_then(|(key, value)| {
+                let value = value.trim();
+                match key.trim() {
+                    "name" => system.name = value.to_string(),
+                    "position" => {
+                        let (x, y) = value.split_once(',')?;
+                        system.position = Vec2::new(x.trim().parse().ok()?, y.trim().parse().ok()?);
+                    }
+                    "next" => {
+                        system.next = value.split(',').map(|id| id.trim().to_string()).collect()
+                    }
+                    "sunlight" => system.sunlight = value.parse().ok()?,
+                    "reserves" => system.reserves = value.parse().ok()?,
+                    "phase" => system.phase = value.parse().ok()?,
+                    "start" => {
+                        for item in value.split(',') {
+                            let (var, amt) = item.trim().split_once(' ')?;
+                            system.start = system.start.clone()
+                                + Bunch::single(
+                                    variant_named(ResourceVariant::iter(), var)?,
+                                    amt.parse().ok()?,
+                                );
+                        }
+                    }
+                    "objective" => system.objectives.push(Objective::parse(value)?),
+                    _ => return None,
+                }
+                Some(())
+            });
+            if parsed.is_none() {
+                warn!("bad line in system {}: {}", id, line);
             }
         }
+        system
     }
-}
 
-fn on_build_construction(
-    mut events: EventReader<BuildConstruction>,
-    mut commands: Commands,
-    map: Res<Map>,
-    handles: Res<AssetHandles>,
-) {
-    for event in events.iter() {
-        let pos = map.positions.get(&event.node_id).unwrap().extend(1.);
-        commands.spawn((
-            NodeIdMarker {
-                node_id: event.node_id.clone(),
-            },
-            SpriteSheetBundle {
-                transform: Transform::default().with_translation(pos),
-                sprite: TextureAtlasSprite {
-                    index: event.var.get_sprite_index(),
-                    ..Default::default()
-                },
-                texture_atlas: handles.atlas.clone(),
-                ..Default::default()
-            },
-        ));
+    fn all() -> Vec<Self> {
+        SYSTEMS
+            .iter()
+            .map(|&(id, source)| Self::parse(id, source))
+            .collect()
     }
-}
 
-fn on_modify_resource(
-    mut commands: Commands,
-    mut events: EventReader<ModifyResource>,
-    mut stock_q: Query<(Entity, &NodeIdMarker, &Children)>,
-    mut text_q: Query<&mut Text>,
-    map: Res<Map>,
-    handles: Res<AssetHandles>,
-) {
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::BLACK,
-    };
-    let text_alignment = TextAlignment::Center;
+    fn map(&self) -> Map {
+        let mut map = Map::test();
+        map.name = self.id.to_string();
+        map.brightness = self.sunlight as f32 / 100.;
+        for deposit in map.deposits.values_mut().flat_map(|d| d.values_mut()) {
+            *deposit = Deposit::new(deposit.initial * self.reserves / 100);
+        }
+        for orbit in map.orbits.values_mut() {
+            orbit.phase += self.phase;
+        }
+        map.update_orbits(0);
+        map
+    }
 
-    for event in events.iter() {
-        if let Some((ent, _, children)) = stock_q
-            .iter_mut()
-            .find(|(_, marker, _)| marker.node_id == event.node_id)
-        {
-            if event.abs > 0 {
-                let mut text = text_q.get_mut(children[0]).unwrap();
-                text.sections[0].value = format!("{}", event.abs);
-            } else {
-                commands.entity(ent).despawn_recursive();
-            }
-        } else {
-            let pos = map.positions.get(&event.node_id).unwrap().extend(1.);
-            commands
-                .spawn((
-                    NodeIdMarker {
-                        node_id: event.node_id.clone(),
-                    },
-                    SpriteSheetBundle {
-                        transform: Transform::default().with_translation(pos),
-                        sprite: TextureAtlasSprite {
-                            index: event.var.get_sprite_index(),
-                            ..Default::default()
-                        },
-                        texture_atlas: handles.atlas.clone(),
-                        ..Default::default()
-                    },
-                ))
-                .with_children(|builder| {
-                    builder.spawn((Text2dBundle {
-                        text: Text::from_section(format!("{}", event.abs), text_style.clone())
-                            .with_alignment(text_alignment),
-                        transform: Transform::default().with_translation(Vec3::new(0., 0., 2.)),
-                        ..default()
-                    },));
-                    builder.spawn(SpriteSheetBundle {
-                        transform: Transform::default().with_translation(Vec3::new(0., 0., 1.8)),
-                        sprite: TextureAtlasSprite {
-                            index: 1,
-                            ..Default::default()
-                        },
-                        texture_atlas: handles.atlas.clone(),
-                        ..Default::default()
-                    });
-                });
+    /// the cargo brought from the last system is loaded on top of the usual start
+    fn scenario(&self, cargo: &Bunch) -> Scenario {
+        Scenario {
+            objectives: self.objectives.clone(),
+            start: self.start.clone() + cargo.clone(),
+            ..Scenario::test()
         }
     }
 }
 
-#[derive(Component, Debug, Clone, Default)]
-pub struct SpriteInterpolationFx {
-    from: Transform,
-    to: Transform,
-    mid: Option<Transform>,
-    timer: Timer,
+const CAMPAIGN_FILE: &str = "campaign.txt";
+
+/// progress through the systems, saved like the profile
+#[derive(Resource, Debug, Clone, Default)]
+struct Campaign {
+    /// the system being played, None outside the campaign
+    current: Option<String>,
+    won: Vec<String>,
+    /// fusion fuel and food the ship left the last system with
+    cargo: Bunch,
+    /// the tree keeps growing from one system to the next
+    research: Research,
 }
 
-pub fn interpolation_fx(
-    mut commands: Commands,
-    mut fx_query: Query<(Entity, &mut SpriteInterpolationFx, &mut Transform)>,
-    time: Res<Time>,
-) {
-    for (ent, mut fx, mut tr) in fx_query.iter_mut() {
-        fx.timer.tick(time.delta());
-        if fx.timer.finished() {
-            commands.entity(ent).despawn_recursive();
-            continue;
-        }
-        let t = fx.timer.percent();
-        if let Some(mid) = fx.mid {
-            if t < 0.5 {
-                tr.translation = fx.from.translation.lerp(mid.translation, t * 2.0);
-                tr.rotation = fx.from.rotation.lerp(mid.rotation, t * 2.);
-            } else {
-                tr.translation = mid.translation.lerp(fx.to.translation, (t - 0.5) * 2.0);
-                tr.rotation = mid.rotation.lerp(fx.to.rotation, (t - 0.5) * 2.0);
+impl Campaign {
+    fn parse(text: &str) -> Self {
+        let mut campaign = Self {
+            research: Research::parse(text),
+            ..Default::default()
+        };
+        for (key, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            let value = value.trim();
+            match key.trim() {
+                "won" => campaign.won.push(value.to_string()),
+                "cargo" => {
+                    let Some((var, amt)) = value.split_once(' ') else {
+                        continue;
+                    };
+                    if let (Some(var), Ok(amt)) =
+                        (variant_named(ResourceVariant::iter(), var), amt.parse())
+                    {
+                        campaign.cargo = campaign.cargo.clone() + Bunch::single(var, amt);
+                    }
+                }
+                _ => {}
             }
-        } else {
-            tr.translation = fx.from.translation.lerp(fx.to.translation, t);
-            tr.rotation = fx.from.rotation.lerp(fx.to.rotation, t);
         }
+        campaign
+    }
+
+    fn to_text(&self) -> String {
+        let won = self.won.iter().map(|id| format!("won = {}\n", id));
+        let cargo = ResourceVariant::iter().filter_map(|var| {
+            let amt = self.cargo.res.get(&var)?;
+            Some(format!("cargo = {:?} {}\n", var, amt))
+        });
+        won.chain(cargo).collect::<String>() + &self.research.to_text()
+    }
+
+    fn load() -> Self {
+        read_local(CAMPAIGN_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        write_local(CAMPAIGN_FILE, &self.to_text());
+    }
+
+    /// the first system, then every one linked from a won system
+    fn reachable(&self, systems: &[StarSystem]) -> Vec<&'static str> {
+        systems
+            .iter()
+            .enumerate()
+            .filter(|(i, system)| {
+                !self.won.iter().any(|id| id == system.id)
+                    && ((*i == 0 && self.won.is_empty())
+                        || systems.iter().any(|from| {
+                            self.won.iter().any(|id| id == from.id)
+                                && from.next.iter().any(|id| id == system.id)
+                        }))
+            })
+            .map(|(_, system)| system.id)
+            .collect()
+    }
+
+    /// the fuel and food on the ship and the research go to the next system
+    fn win(&mut self, map: &Map, research: &Research) {
+        let Some(id) = self.current.clone() else {
+            return;
+        };
+        if !self.won.contains(&id) {
+            self.won.push(id);
+        }
+        let ship = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+        self.cargo = Bunch {
+            res: ship
+                .res
+                .into_iter()
+                .filter(|(var, _)| {
+                    matches!(var, ResourceVariant::FusionFuel | ResourceVariant::Food)
+                })
+                .collect(),
+        };
+        self.research = research.clone();
     }
 }
 
-fn on_modify_resource_fx(
-    mut commands: Commands,
-    mut events: EventReader<ModifyResourceFx>,
-    map: Res<Map>,
-    handles: Res<AssetHandles>,
-) {
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::BLACK,
-    };
-    let text_alignment = TextAlignment::Center;
+/// what wins and loses the current game
+#[derive(Resource, Debug, Clone)]
+struct Scenario {
+    objectives: Vec<Objective>,
+    /// the ship's hold when the game starts
+    start: Bunch,
+    /// planets the ship has orbited
+    visited: Vec<GroupId>,
+}
 
-    for event in events.iter() {
-        let from = map.positions.get(&event.from).unwrap().extend(1.);
-        let to = map.positions.get(&event.to).unwrap().extend(1.);
-        commands
-            .spawn((
-                SpriteSheetBundle {
-                    transform: Transform::default().with_translation(from),
-                    sprite: TextureAtlasSprite {
-                        index: event.var.get_sprite_index(),
-                        ..Default::default()
-                    },
-                    texture_atlas: handles.atlas.clone(),
-                    ..Default::default()
-                },
-                SpriteInterpolationFx {
-                    from: Transform::default().with_translation(from),
-                    to: Transform::default().with_translation(to),
-                    mid: Some(
-                        Transform::default()
-                            .with_translation((from + to) / 2. + Vec3::new(0., 16., 0.)),
-                    ),
-                    timer: Timer::new(Duration::from_millis(300), TimerMode::Once),
-                },
-            ))
-            .with_children(|builder| {
-                builder.spawn((Text2dBundle {
-                    text: Text::from_section(format!("{}", event.diff), text_style.clone())
-                        .with_alignment(text_alignment),
-                    transform: Transform::default().with_translation(Vec3::new(0., 0., 2.)),
-                    ..default()
-                },));
-                builder.spawn(SpriteSheetBundle {
-                    transform: Transform::default().with_translation(Vec3::new(0., 0., 1.8)),
-                    sprite: TextureAtlasSprite {
-                        index: 1,
-                        ..Default::default()
-                    },
-                    texture_atlas: handles.atlas.clone(),
-                    ..Default::default()
-                });
-            });
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::test()
     }
 }
 
-#[derive(Debug, Clone, Component)]
-struct Node {
-    id: NodeId,
-}
-#[derive(Debug, Clone, Component)]
-struct Planet {
-    id: GroupId,
-}
-#[derive(Debug, Clone, Component)]
-struct Ship {
-    orbiting_group: GroupId,
-    own_group: GroupId,
-    planned_move: Option<GroupId>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum ResourceVariant {
-    Power,
-    RocketFuel,
-    Food,
-    Material,
-    FusionFuel,
-}
-
-impl ResourceVariant {
-    fn get_sprite_index(&self) -> usize {
-        match self {
-            ResourceVariant::Power => 8,
-            ResourceVariant::RocketFuel => 9,
-            ResourceVariant::Food => 10,
-            ResourceVariant::Material => 11,
-            ResourceVariant::FusionFuel => 12,
+impl Scenario {
+    /// one objective per line
+    fn parse(source: &str) -> Self {
+        let objectives = source
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let objective = Objective::parse(line);
+                if objective.is_none() {
+                    warn!("bad objective: {}", line);
+                }
+                objective
+            })
+            .collect();
+        Self {
+            objectives,
+            start: Bunch::many(&[
+                (ResourceVariant::FusionFuel, 20),
+                (ResourceVariant::Material, 20),
+                (ResourceVariant::Food, 20),
+            ]),
+            visited: vec![GroupId(1)],
         }
     }
-}
 
-impl ToString for ResourceVariant {
-    fn to_string(&self) -> String {
-        match self {
-            ResourceVariant::Power => "Power",
-            ResourceVariant::RocketFuel => "Rocket Fuel",
-            ResourceVariant::Food => "Food",
-            ResourceVariant::Material => "Material",
-            ResourceVariant::FusionFuel => "Fusion Fuel",
+    /// bring the fuel and food home and don't starve
+    fn test() -> Self {
+        Self::parse(include_str!("../assets/scenario.txt"))
+    }
+
+    fn visit(&mut self, group: &GroupId) {
+        if !self.visited.contains(group) {
+            self.visited.push(group.clone());
         }
-        .to_string()
     }
-}
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
-struct Bunch {
-    res: HashMap<ResourceVariant, u32>,
-}
+    /// amount of the resource the ship has to carry, if any
+    fn target(&self, var: &ResourceVariant) -> Option<u32> {
+        self.objectives
+            .iter()
+            .find_map(|objective| match objective {
+                Objective::Stock(v, amt) if v == var => Some(*amt),
+                _ => None,
+            })
+    }
 
-impl Bunch {
-    fn single(var: ResourceVariant, amt: u32) -> Self {
-        Self {
-            res: HashMap::from([(var, amt)]),
+    fn status(&self, objective: &Objective, turn: u32, map: &Map, crew: &Crew) -> Status {
+        let ship = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+        let holds = |ok: bool| if ok { Status::Done } else { Status::Failed };
+        match objective {
+            // the ship needs more than the amount, like the original win check
+            Objective::Stock(var, amt) => {
+                if ship.res.get(var).unwrap_or(&0) > amt {
+                    Status::Done
+                } else {
+                    Status::Pending
+                }
+            }
+            Objective::Reach(group, by) => {
+                if self.visited.contains(group) {
+                    Status::Done
+                } else if turn > *by {
+                    Status::Failed
+                } else {
+                    Status::Pending
+                }
+            }
+            Objective::Keep(var, group) => {
+                holds(map.groups.get(group).into_iter().flatten().any(|id| {
+                    match map.occupation.get(id) {
+                        Some(NodeOccupant::Construction { var: v, .. }) => v == var,
+                        _ => false,
+                    }
+                }))
+            }
+            Objective::MinFood(food) => {
+                holds(ship.res.get(&ResourceVariant::Food).unwrap_or(&0) >= food)
+            }
+            Objective::TurnLimit(limit) => holds(turn < *limit),
+            Objective::Crew => holds(crew.awake > 0),
         }
     }
 
-    fn contains(&self, oth: &Bunch) -> bool {
-        oth.res.iter().all(|(var, amt)| {
-            let Some(cur) = self.res.get(var) else {
-                return false;
-            };
-            cur >= amt
-        })
+    /// Some(true) when every goal is done, Some(false) when anything failed.
+    /// winning comes first, so the goals can be reached on the last turn.
+    fn outcome(&self, turn: u32, map: &Map, crew: &Crew) -> Option<bool> {
+        let statuses: Vec<(bool, Status)> = self
+            .objectives
+            .iter()
+            .map(|objective| (objective.is_goal(), self.status(objective, turn, map, crew)))
+            .collect();
+        let mut goals = statuses.iter().filter(|(goal, _)| *goal).peekable();
+        if goals.peek().is_some() && goals.all(|(_, status)| *status == Status::Done) {
+            return Some(true);
+        }
+        if statuses.iter().any(|(_, status)| *status == Status::Failed) {
+            return Some(false);
+        }
+        None
     }
 }
+/// stockpiles touched by a transfer: node, amount left there, change
+type StockChanges = Vec<(NodeId, u32, i32)>;
 
-impl core::ops::Add for Bunch {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        let mut out = self.clone();
-        for (var, amt) in rhs.res.iter() {
-            if let Some(sum) = out.res.get_mut(var) {
-                *sum += amt;
-            } else {
-                out.res.insert(var.clone(), *amt);
-            }
+impl Map {
+    fn test() -> Self {
+        let mut planets_pos = vec![
+            (GroupId(0), Vec2::new(0., -256.)),
+            (GroupId(1), Vec2::new(-200., 0.)),
+            (GroupId(2), Vec2::new(0., -60.)),
+            (GroupId(3), Vec2::new(160., -40.)),
+            (GroupId(4), Vec2::new(160., 160.)),
+            (GroupId(5), Vec2::new(-160., 240.)),
+            (GroupId(6), Vec2::new(-320., 80.)),
+            (GroupId(7), Vec2::new(-440., -20.)),
+        ];
+        let off = Vec2::new(-32., 16.);
+        for (_, pos) in planets_pos.iter_mut() {
+            *pos += off;
         }
-        out
+        let w = 64.;
+        let s = 64.;
+        let wh = w / 2.;
+        let node_pos = vec![
+            //
+            planets_pos[0].1 + Vec2::new(-192., 0.),
+            planets_pos[0].1 + Vec2::new(-128., 0.),
+            planets_pos[0].1 + Vec2::new(-64., 0.),
+            planets_pos[0].1 + Vec2::new(0., 0.),
+            planets_pos[0].1 + Vec2::new(64., 0.),
+            //
+            planets_pos[1].1 + Vec2::new(s, 0.),
+            planets_pos[1].1 + Vec2::new(s + w, 0.),
+            planets_pos[1].1 + Vec2::new(s + w / 2., w),
+            //
+            planets_pos[2].1 + Vec2::new(-wh, s),
+            planets_pos[2].1 + Vec2::new(wh, s),
+            planets_pos[2].1 + Vec2::new(wh, s + w),
+            planets_pos[2].1 + Vec2::new(-wh, s + w),
+            //
+            planets_pos[3].1 + Vec2::new(s, wh),
+            planets_pos[3].1 + Vec2::new(s, -wh),
+            planets_pos[3].1 + Vec2::new(s + w, wh),
+            planets_pos[3].1 + Vec2::new(s + w, -wh),
+            //
+            planets_pos[4].1 + Vec2::new(-wh - w, s),
+            planets_pos[4].1 + Vec2::new(-wh, s),
+            planets_pos[4].1 + Vec2::new(wh, s),
+            planets_pos[4].1 + Vec2::new(wh + w, s),
+            planets_pos[4].1 + Vec2::new(-wh - w, s + w),
+            planets_pos[4].1 + Vec2::new(-wh, s + w),
+            planets_pos[4].1 + Vec2::new(wh, s + w),
+            planets_pos[4].1 + Vec2::new(wh + w, s + w),
+            //
+            planets_pos[5].1 + Vec2::new(-wh, -s),
+            planets_pos[5].1 + Vec2::new(wh, -s),
+            //
+            planets_pos[6].1 + Vec2::new(s, 0.),
+            planets_pos[6].1 + Vec2::new(s + w, 0.),
+            planets_pos[6].1 + Vec2::new(s + w + w, 0.),
+            //
+            planets_pos[7].1 + Vec2::new(-w, s),
+            planets_pos[7].1 + Vec2::new(0., s),
+            planets_pos[7].1 + Vec2::new(w, s),
+            planets_pos[7].1 + Vec2::new(-w, s + w),
+            planets_pos[7].1 + Vec2::new(0., s + w),
+        ];
+
+        let mut map = Self {
+            name: "test".to_string(),
+            brightness: 1.,
+            groups: HashMap::from([
+                (GroupId(0), (0..5).map(NodeId).collect()),
+                (GroupId(1), (5..8).map(NodeId).collect()),
+                (GroupId(2), (8..12).map(NodeId).collect()),
+                (GroupId(3), (12..16).map(NodeId).collect()),
+                (GroupId(4), (16..24).map(NodeId).collect()),
+                (GroupId(5), (24..26).map(NodeId).collect()),
+                (GroupId(6), (26..29).map(NodeId).collect()),
+                (GroupId(7), (29..34).map(NodeId).collect()),
+            ]),
+            edges: vec![(GroupId(0), GroupId(1))],
+            lanes: vec![
+                (GroupId(1), GroupId(2)),
+                (GroupId(1), GroupId(5)),
+                (GroupId(1), GroupId(6)),
+                (GroupId(2), GroupId(3)),
+                (GroupId(3), GroupId(4)),
+                (GroupId(4), GroupId(5)),
+                (GroupId(5), GroupId(6)),
+                (GroupId(6), GroupId(7)),
+            ],
+            positions: HashMap::from_iter(
+                node_pos
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, pos)| (NodeId(i), pos)),
+            ),
+            group_positions: HashMap::from_iter(planets_pos),
+            occupation: HashMap::default(),
+            fired: vec![],
+            // the asteroids share the belt, the planets drift in and out of reach
+            orbits: HashMap::from([
+                (
+                    GroupId(1),
+                    Orbit {
+                        radius: 2.8,
+                        phase: 0.,
+                    },
+                ),
+                (
+                    GroupId(2),
+                    Orbit {
+                        radius: 1.5,
+                        phase: 30.,
+                    },
+                ),
+                (
+                    GroupId(3),
+                    Orbit {
+                        radius: 2.0,
+                        phase: 80.,
+                    },
+                ),
+                (
+                    GroupId(4),
+                    Orbit {
+                        radius: 5.2,
+                        phase: 150.,
+                    },
+                ),
+                (
+                    GroupId(5),
+                    Orbit {
+                        radius: 2.8,
+                        phase: 40.,
+                    },
+                ),
+                (
+                    GroupId(6),
+                    Orbit {
+                        radius: 2.8,
+                        phase: -40.,
+                    },
+                ),
+                (
+                    GroupId(7),
+                    Orbit {
+                        radius: 7.0,
+                        phase: -90.,
+                    },
+                ),
+            ]),
+            turn: 0,
+            kinds: HashMap::from([
+                (GroupId(1), BodyKind::Asteroid),
+                (GroupId(2), BodyKind::Rocky),
+                (GroupId(3), BodyKind::Rocky),
+                (GroupId(4), BodyKind::GasGiant),
+                (GroupId(5), BodyKind::Asteroid),
+                (GroupId(6), BodyKind::Asteroid),
+                (GroupId(7), BodyKind::GasGiant),
+            ]),
+            // the ship wakes up orbiting the first asteroid
+            scouted: vec![GroupId(1)],
+            // asteroids and rocky planets hold ore and ice, gas giants helium
+            deposits: HashMap::from([
+                (
+                    GroupId(1),
+                    Map::deposits(&[(ResourceVariant::Ore, 200), (ResourceVariant::Water, 60)]),
+                ),
+                (
+                    GroupId(2),
+                    Map::deposits(&[(ResourceVariant::Ore, 400), (ResourceVariant::Water, 40)]),
+                ),
+                (
+                    GroupId(3),
+                    Map::deposits(&[(ResourceVariant::Ore, 250), (ResourceVariant::Water, 200)]),
+                ),
+                (
+                    GroupId(4),
+                    Map::deposits(&[
+                        (ResourceVariant::Helium3, 200),
+                        (ResourceVariant::Helium4, 400),
+                    ]),
+                ),
+                (
+                    GroupId(5),
+                    Map::deposits(&[(ResourceVariant::Ore, 300), (ResourceVariant::Water, 100)]),
+                ),
+                (
+                    GroupId(6),
+                    Map::deposits(&[(ResourceVariant::Ore, 200), (ResourceVariant::Water, 150)]),
+                ),
+                (
+                    GroupId(7),
+                    Map::deposits(&[
+                        (ResourceVariant::Helium3, 300),
+                        (ResourceVariant::Helium4, 600),
+                    ]),
+                ),
+            ]),
+        };
+        map.update_orbits(0);
+        map
     }
-}
 
-impl std::iter::Sum<Self> for Bunch {
-    fn sum<I>(iter: I) -> Self
-    where
-        I: Iterator<Item = Self>,
-    {
-        iter.fold(Self::default(), |a, b| a + b)
+    fn deposits(res: &[(ResourceVariant, u32)]) -> HashMap<ResourceVariant, Deposit> {
+        res.iter()
+            .map(|(var, amt)| (var.clone(), Deposit::new(*amt)))
+            .collect()
     }
-}
 
-#[derive(Debug, Clone)]
-enum ConstructionVariant {
-    SolarField,
-    AtmosphereHarvester,
-    ChemicalPlant,
-    PlanetFarm,
-    AsteroidMine,
-    Quarry,
-    PowerPlant,
-}
+    /// what is left in the ground of a group
+    fn reserves(&self, group_id: &GroupId) -> Bunch {
+        self.deposits
+            .get(group_id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, deposit)| deposit.left > 0)
+            .map(|(var, deposit)| Bunch::single(var.clone(), deposit.left))
+            .sum()
+    }
 
-impl ConstructionVariant {
-    fn get_sprite_index(&self) -> usize {
-        match self {
-            Self::SolarField => 16,
-            Self::AtmosphereHarvester => 17,
-            Self::ChemicalPlant => 18,
-            Self::PlanetFarm => 19,
-            Self::AsteroidMine => 20,
-            Self::Quarry => 21,
-            Self::PowerPlant => 22,
+    /// extractors need something left to dig, the other buildings always can
+    fn can_extract(&self, group_id: &GroupId, var: &ConstructionVariant) -> bool {
+        if !var.is_extractor() {
+            return true;
         }
+        let reserves = self.reserves(group_id);
+        var.produce_resources()
+            .res
+            .keys()
+            .any(|res| reserves.res.contains_key(res))
     }
 
-    fn get_material_cost(&self) -> u32 {
-        match self {
-            Self::SolarField => 5,
-            Self::AtmosphereHarvester => 20,
-            Self::ChemicalPlant => 3,
-            Self::PlanetFarm => 10,
-            Self::AsteroidMine => 2,
-            Self::Quarry => 5,
-            Self::PowerPlant => 5,
+    /// sunlight relative to the belt, it falls with the square of the distance to the star.
+    /// the ship gets the light of the planet it orbits plus a bonus.
+    fn irradiance(&self, group_id: &GroupId) -> f32 {
+        if *group_id == GroupId(0) {
+            return self
+                .star(group_id)
+                .first()
+                .map_or(1., |planet| self.irradiance(planet))
+                * SHIP_SUNLIGHT_BONUS;
         }
+        self.brightness
+            * self
+                .orbits
+                .get(group_id)
+                .map_or(1., |orbit| (SUNLIGHT_AU / orbit.radius).powi(2))
     }
 
-    fn request_resources(&self) -> Bunch {
-        match self {
-            Self::SolarField => Bunch::default(),
-            Self::AtmosphereHarvester => Bunch::single(ResourceVariant::Power, 50),
-            Self::ChemicalPlant => Bunch::single(ResourceVariant::Material, 2),
-            Self::PlanetFarm => Bunch::single(ResourceVariant::Material, 12),
-            Self::AsteroidMine => Bunch::single(ResourceVariant::RocketFuel, 2),
-            Self::Quarry => Bunch::single(ResourceVariant::Power, 45),
-            Self::PowerPlant => Bunch::single(ResourceVariant::RocketFuel, 2),
+    /// what a construction would make in a group, before digging into deposits
+    fn output(&self, group_id: &GroupId, var: &ConstructionVariant, tier: u32) -> Bunch {
+        let mut output = var.produce_at(tier);
+        if *var == ConstructionVariant::SolarField {
+            let irradiance = self.irradiance(group_id);
+            for amt in output.res.values_mut() {
+                *amt = (*amt as f32 * irradiance).round() as u32;
+            }
         }
+        output
     }
 
-    fn produce_resources(&self) -> Bunch {
-        match self {
-            Self::SolarField => Bunch::single(ResourceVariant::Power, 3),
-            Self::AtmosphereHarvester => Bunch::single(ResourceVariant::FusionFuel, 10),
-            Self::ChemicalPlant => Bunch::single(ResourceVariant::RocketFuel, 4),
-            Self::PlanetFarm => Bunch::single(ResourceVariant::Food, 10),
-            Self::AsteroidMine => Bunch::single(ResourceVariant::Material, 5),
-            Self::Quarry => Bunch::single(ResourceVariant::Material, 30),
-            Self::PowerPlant => Bunch::single(ResourceVariant::Power, 10),
-        }
+    fn node_output(&self, id: &NodeId, var: &ConstructionVariant, tier: u32) -> Bunch {
+        self.group_from_node(id)
+            .map(|group_id| self.output(&group_id, var, tier))
+            .unwrap_or_else(|_| var.produce_at(tier))
     }
 
-    fn get_cooldown(&self) -> u32 {
-        match self {
-            Self::AtmosphereHarvester => 3,
-            Self::PlanetFarm => 2,
-            Self::Quarry => 3,
-            _ => 1,
+    /// output of a construction, extractors take it from the group's deposits
+    fn extract(&mut self, group_id: &GroupId, var: &ConstructionVariant, tier: u32) -> Bunch {
+        let output = self.output(group_id, var, tier);
+        if !var.is_extractor() {
+            return output;
         }
+        let Some(deposits) = self.deposits.get_mut(group_id) else {
+            return Bunch::default();
+        };
+        output
+            .res
+            .iter()
+            .filter_map(|(res, amt)| {
+                let got = deposits.get_mut(res)?.extract(*amt);
+                (got > 0).then(|| Bunch::single(res.clone(), got))
+            })
+            .sum()
     }
 
-    fn iter() -> impl Iterator<Item = Self> {
-        [
-            Self::SolarField,
-            Self::AtmosphereHarvester,
-            Self::ChemicalPlant,
-            Self::PlanetFarm,
-            Self::AsteroidMine,
-            Self::Quarry,
-            Self::PowerPlant,
-        ]
-        .iter()
-        .cloned()
+    fn is_scouted(&self, group_id: &GroupId) -> bool {
+        *group_id == GroupId(0) || self.scouted.contains(group_id)
     }
-}
 
-impl ToString for ConstructionVariant {
-    fn to_string(&self) -> String {
-        match &self {
-            Self::SolarField => "Solar Field",
-            Self::AtmosphereHarvester => "Atmosphere Harvester",
-            Self::ChemicalPlant => "Chemical Plant",
-            Self::PlanetFarm => "Farm",
-            Self::AsteroidMine => "Asteroid Miner",
-            Self::Quarry => "Quarry",
-            Self::PowerPlant => "Power Plant",
-        }
-        .to_string()
+    fn is_node_scouted(&self, id: &NodeId) -> bool {
+        self.group_from_node(id)
+            .is_ok_and(|group_id| self.is_scouted(&group_id))
     }
-}
-
-#[derive(Debug, Clone, Component)]
-struct NodeIdMarker {
-    node_id: NodeId,
-}
-
-fn setup_scene(
-    mut commands: Commands,
-    handles: Res<AssetHandles>,
-    mut map: ResMut<Map>,
-    mut event_construct: EventWriter<BuildConstruction>,
-    mut event_produce: EventWriter<ModifyResource>,
-    mut next_state: ResMut<NextState<AppState>>,
-) {
-    commands.spawn(SpriteBundle {
-        texture: handles.ship.clone(),
-        transform: Transform::default().with_translation(Vec3::new(-80., -360. + 128., 0.0)),
-        ..Default::default()
-    });
 
-    // planets and nodes
-    for (id, pos) in map.group_positions.iter() {
-        if id == &GroupId(0) {
-            // that's a ship not a planet
-            continue;
+    /// reveal a group, returns false if it was already known
+    fn scout(&mut self, group_id: &GroupId) -> bool {
+        if self.is_scouted(group_id) {
+            return false;
         }
-        commands.spawn((
-            Transform {
-                translation: pos.extend(0.0),
-                ..Default::default()
-            },
-            Planet { id: id.clone() },
-        ));
-    }
-    for (id, pos) in map.positions.iter() {
-        commands.spawn((
-            SpriteSheetBundle {
-                transform: Transform::default().with_translation(pos.extend(0.0)),
-                sprite: TextureAtlasSprite {
-                    index: 0,
-                    ..Default::default()
-                },
-                texture_atlas: handles.atlas.clone(),
-                ..Default::default()
-            },
-            Node { id: id.clone() },
-        ));
+        self.scouted.push(group_id.clone());
+        true
     }
 
-    // map
-    commands.spawn(SpriteBundle {
-        texture: handles.map.clone(),
-        transform: Transform::default().with_translation(Vec3::new(0., 0., -0.1)),
-        ..Default::default()
-    });
-
-    //ship
-    let group_pos = map
-        .group_positions
-        .get(&GroupId(1))
-        .unwrap()
-        .clone()
-        .extend(0.2);
-    commands.spawn((
-        SpriteSheetBundle {
-            transform: Transform::default()
-                .with_translation(group_pos + Vec3::new(32., 0., 0.))
-                .with_rotation(Quat::from_rotation_z(PI / 2.)),
-            sprite: TextureAtlasSprite {
-                index: 4,
-                ..Default::default()
-            },
-            texture_atlas: handles.atlas.clone(),
-            ..Default::default()
-        },
-        Ship {
-            own_group: GroupId(0),
-            orbiting_group: GroupId(1),
-            planned_move: None,
-        },
-    ));
+    /// what scouting found in a group
+    fn scout_report(&self, group_id: &GroupId) -> String {
+        trf(
+            "scout.report",
+            &[
+                &group_id.0,
+                &self
+                    .kinds
+                    .get(group_id)
+                    .map_or(tr("tooltip.none"), |kind| kind.to_string()),
+                &self.groups.get(group_id).map_or(0, |nodes| nodes.len()),
+                &self.reserves(group_id).describe(),
+            ],
+        )
+    }
 
-    let occ = NodeOccupant::Construction {
-        var: ConstructionVariant::SolarField,
-        cooldown: 0,
-    };
-    map.set_at(&NodeId(0), occ);
-    event_construct.send(BuildConstruction {
-        node_id: NodeId(0),
-        var: ConstructionVariant::SolarField,
-    });
+    fn lane_open_at(&self, a: &GroupId, b: &GroupId, turn: u32) -> bool {
+        let (Some(orbit_a), Some(orbit_b)) = (self.orbits.get(a), self.orbits.get(b)) else {
+            return false;
+        };
+        let diff = (orbit_a.angle(turn) - orbit_b.angle(turn)).rem_euclid(TAU);
+        diff.min(TAU - diff) <= TRANSFER_WINDOW.to_radians()
+    }
 
-    if let Ok(actions) = map.add_resource_in_group(&GroupId(0), &ResourceVariant::FusionFuel, 20) {
-        for (to, abs, _diff) in actions {
-            event_produce.send(ModifyResource {
-                node_id: to.clone(),
-                var: ResourceVariant::FusionFuel,
-                abs,
-            });
+    /// move the planets to the given turn and link the lanes that are open.
+    /// the ship stays linked to the planet it orbits.
+    fn update_orbits(&mut self, turn: u32) {
+        self.turn = turn;
+        for (group_id, orbit) in self.orbits.iter() {
+            let pos = orbit.screen_position(turn);
+            let old = self.group_positions.insert(group_id.clone(), pos);
+            // the nodes keep their place around the planet
+            let delta = pos - old.unwrap_or(pos);
+            for id in self.groups.get(group_id).into_iter().flatten() {
+                if let Some(node_pos) = self.positions.get_mut(id) {
+                    *node_pos += delta;
+                }
+            }
         }
+        let open: Vec<(GroupId, GroupId)> = self
+            .lanes
+            .iter()
+            .filter(|(a, b)| self.lane_open_at(a, b, turn))
+            .cloned()
+            .collect();
+        self.edges
+            .retain(|(a, b)| *a == GroupId(0) || *b == GroupId(0));
+        self.edges.extend(open);
     }
 
-    if let Ok(actions) = map.add_resource_in_group(&GroupId(0), &ResourceVariant::Material, 20) {
-        for (to, abs, _diff) in actions {
-            event_produce.send(ModifyResource {
-                node_id: to.clone(),
-                var: ResourceVariant::Material,
-                abs,
-            });
-        }
+    /// link the ship's group only to the planet it orbits
+    fn dock(&mut self, ship: &GroupId, planet: &GroupId) {
+        self.edges.retain(|edge| edge.0 != *ship && edge.1 != *ship);
+        self.edges.push((ship.clone(), planet.clone()));
     }
 
-    if let Ok(actions) = map.add_resource_in_group(&GroupId(0), &ResourceVariant::Food, 20) {
-        for (to, abs, _diff) in actions {
-            event_produce.send(ModifyResource {
-                node_id: to.clone(),
-                var: ResourceVariant::Food,
-                abs,
-            });
+    /// fusion fuel for the ship to jump between two planets this turn
+    fn jump_cost(&self, a: &GroupId, b: &GroupId) -> u32 {
+        match (self.orbits.get(a), self.orbits.get(b)) {
+            (Some(orbit_a), Some(orbit_b)) => {
+                let au = orbit_a
+                    .position(self.turn)
+                    .distance(orbit_b.position(self.turn));
+                ((au / JUMP_AU).ceil() as u32).max(1)
+            }
+            _ => 1,
         }
     }
 
-    next_state.set(AppState::Gameplay);
-}
-
-fn ship_orbit(mut query_ship: Query<(&mut Transform, &Ship)>, map: Res<Map>, time: Res<Time>) {
-    if let Ok((mut tr, ship)) = query_ship.get_single_mut() {
-        let group_pos = map.group_positions.get(&ship.orbiting_group).unwrap();
-        tr.rotate_around(
-            group_pos.extend(0.),
-            Quat::from_rotation_z(0.1 * time.delta_seconds()),
-        );
+    /// turns until the lane opens (0 if open now) and for how long it stays open,
+    /// None if it stays closed for the whole lookahead
+    fn next_window(&self, a: &GroupId, b: &GroupId, lookahead: u32) -> Option<(u32, u32)> {
+        let open = |t: &u32| self.lane_open_at(a, b, self.turn + t);
+        let start = (0..lookahead).find(open)?;
+        let length = (start..lookahead).take_while(open).count() as u32;
+        Some((start, length))
     }
-}
-
-#[derive(Component)]
-struct UiShipPlanMarker;
 
-fn ship_plan(
-    mut commands: Commands,
-    handles: Res<AssetHandles>,
-    query_m: Query<(Entity, &UiShipPlanMarker)>,
-    query_ship: Query<&Ship>,
-    map: Res<Map>,
-) {
-    for (e, _) in query_m.iter() {
-        commands.entity(e).despawn_recursive();
+    fn star(&self, group_id: &GroupId) -> Vec<GroupId> {
+        self.edges
+            .iter()
+            .filter_map(|edge| match edge {
+                (n, m) if n == group_id => Some(m.clone()),
+                (m, n) if n == group_id => Some(m.clone()),
+                _ => None,
+            })
+            .collect()
     }
-    if let Ok(ship) = query_ship.get_single() {
-        if let Some(plan) = &ship.planned_move {
-            let group_pos = map.group_positions.get(plan).unwrap();
-            commands.spawn((
-                SpriteSheetBundle {
-                    transform: Transform::default().with_translation(group_pos.extend(0.1)),
-                    sprite: TextureAtlasSprite {
-                        color: Color::RED,
-                        index: 5,
-                        ..Default::default()
-                    },
-                    texture_atlas: handles.atlas.clone(),
-                    ..Default::default()
-                },
-                UiShipPlanMarker,
-            ));
+
+    /// number of jumps between two planets, the ship's group is not a valid hop.
+    fn distance(&self, from: &GroupId, to: &GroupId) -> Option<u32> {
+        let mut visited = vec![from.clone()];
+        let mut frontier = vec![from.clone()];
+        let mut dist = 0;
+        while !frontier.is_empty() {
+            if frontier.contains(to) {
+                return Some(dist);
+            }
+            dist += 1;
+            let mut next = vec![];
+            for group_id in frontier.iter() {
+                for neigh in self.star(group_id) {
+                    if neigh == GroupId(0) || visited.contains(&neigh) {
+                        continue;
+                    }
+                    visited.push(neigh.clone());
+                    next.push(neigh);
+                }
+            }
+            frontier = next;
         }
+        None
     }
-}
 
-fn send_end_turn(keys: Res<Input<KeyCode>>, mut events: EventWriter<EndTurn>) {
-    if keys.just_pressed(KeyCode::Delete) {
-        events.send(EndTurn);
+    fn group_from_node(&self, id: &NodeId) -> Result<GroupId, MapError> {
+        self.groups
+            .iter()
+            .find(|(_, ids)| ids.contains(id))
+            .map(|(group_id, _)| group_id.clone())
+            .ok_or(MapError::UnknownNode(id.clone()))
     }
-}
 
-#[derive(Resource, Clone, Debug)]
-struct AutoActions {
-    actions: Vec<AutoAction>,
-    current: Option<AutoAction>,
-    timer: Timer,
-}
+    fn set_at(&mut self, id: &NodeId, occ: NodeOccupant) {
+        self.occupation.insert(id.clone(), occ);
+    }
 
-impl Default for AutoActions {
-    fn default() -> Self {
-        Self {
-            actions: vec![],
-            current: None,
-            timer: Timer::new(Duration::from_millis(300), TimerMode::Repeating),
-        }
+    fn get_group_bunch(&self, id: &GroupId) -> Result<Bunch, MapError> {
+        let group = self
+            .groups
+            .get(id)
+            .ok_or(MapError::UnknownGroup(id.clone()))?;
+        Ok(group
+            .iter()
+            .filter_map(|node_id| match self.occupation.get(node_id) {
+                Some(NodeOccupant::Stockpile { var, amt }) => {
+                    Some(Bunch::single(var.clone(), *amt))
+                }
+                _ => None,
+            })
+            .sum())
     }
-}
 
-impl AutoActions {
-    fn done(&self) -> bool {
-        self.actions.is_empty() && self.current.is_none()
+    fn get_lowest_stockpile(&self, id: &GroupId, v: &ResourceVariant) -> Result<NodeId, MapError> {
+        #[cfg(feature = "dbtrace")]
+        println!("getting lowest stockpile for {:?} {:?}", id, v);
+        let group = self
+            .groups
+            .get(id)
+            .ok_or(MapError::UnknownGroup(id.clone()))?;
+        let node_id = group
+            .iter()
+            .filter_map(|node_id| match self.occupation.get(node_id) {
+                Some(NodeOccupant::Stockpile { var, amt }) if v == var => Some((node_id, amt)),
+                _ => None,
+            })
+            .min_by(|a, b| a.1.cmp(b.1))
+            .ok_or(MapError::InsufficientResources(v.clone()))?
+            .0
+            .clone();
+        #[cfg(feature = "dbtrace")]
+        println!("found lowest stockpile at {:?}", node_id);
+        Ok(node_id)
     }
-}
 
-#[derive(Resource, Clone, Debug)]
-enum AutoAction {
-    ConsumeResource {
-        from: NodeId,
-        to: NodeId,
-        var: ResourceVariant,
-        abs: u32,
-        diff: i32,
-    },
-    ProduceResource {
-        from: NodeId,
-        to: NodeId,
-        var: ResourceVariant,
-        abs: u32,
-        diff: i32,
-    },
-    ShipMove {
-        from: GroupId,
-        to: GroupId,
-    },
-}
-
-#[derive(Resource, Debug, Clone)]
-struct TurnCount {
-    count: u32,
-}
-
-impl Default for TurnCount {
-    fn default() -> Self {
-        Self { count: 1 }
-    }
-}
-
-fn turn(
-    mut events: EventReader<EndTurn>,
-    mut map: ResMut<Map>,
-    mut autoactions: ResMut<AutoActions>,
-    mut ship_q: Query<&mut Ship>,
-    mut turns: ResMut<TurnCount>,
-    mut next_state: ResMut<NextState<AppState>>,
-) {
-    if !autoactions.done() {
-        return;
-    }
-    for _ in events.iter() {
-        turns.count += 1;
-        for (_id, occ) in map.occupation.iter_mut() {
-            match occ {
-                NodeOccupant::Construction { cooldown, .. } if *cooldown > 0 => {
-                    *cooldown -= 1;
-                }
-                _ => {}
+    /// add to highest stockpile until amt = 0 or put in empty.
+    /// returns the changed stockpiles and the amount that didn't fit.
+    fn add_resource_in_group(
+        &mut self,
+        group_id: &GroupId,
+        v: &ResourceVariant,
+        amt: u32,
+    ) -> Result<(StockChanges, u32), MapError> {
+        #[cfg(feature = "dbtrace")]
+        println!("adding to {:?} {:?} {:?}", group_id, v, amt);
+        let group = self
+            .groups
+            .get(group_id)
+            .ok_or(MapError::UnknownGroup(group_id.clone()))?
+            .clone();
+        let mut left = amt;
+        let mut actions = vec![];
+        while left > 0 {
+            // is there already a pile?
+            if let Some(node_id) = group
+                .iter()
+                .filter_map(|node_id| match self.occupation.get(node_id) {
+                    Some(NodeOccupant::Stockpile { var, amt })
+                        if (v == var && *amt < MAX_STOCKPILE) =>
+                    {
+                        Some((node_id, *amt))
+                    }
+                    _ => None,
+                })
+                .max_by(|a, b| a.1.cmp(&b.1))
+            {
+                // insert into highest
+                let Some(NodeOccupant::Stockpile { amt: stock_amt, .. }) =
+                    self.occupation.get_mut(node_id.0)
+                else {
+                    return Err(MapError::UnknownNode(node_id.0.clone()));
+                };
+                let clamped = left.min(MAX_STOCKPILE - *stock_amt);
+                actions.push((node_id.0.clone(), clamped + *stock_amt, clamped as i32));
+                *stock_amt += clamped;
+                left -= clamped;
+            } else if let Some(empty_id) = group
+                .iter()
+                .find(|node_id| self.occupation.get(*node_id).is_none())
+            {
+                // insert into an eventual empty tile
+                let clamped = left.min(MAX_STOCKPILE);
+                actions.push((empty_id.clone(), clamped, clamped as i32));
+                self.set_at(
+                    empty_id,
+                    NodeOccupant::Stockpile {
+                        var: v.clone(),
+                        amt: clamped,
+                    },
+                );
+                left -= clamped;
+            } else {
+                break;
             }
         }
-        let mut constructions: Vec<(NodeId, ConstructionVariant)> = map
-            .occupation
+        Ok((actions, left))
+    }
+
+    /// room left for the resource in the group
+    fn room_for(&self, group_id: &GroupId, v: &ResourceVariant) -> u32 {
+        let Some(group) = self.groups.get(group_id) else {
+            return 0;
+        };
+        group
             .iter()
-            .filter_map(|(id, occ)| match occ {
-                NodeOccupant::Construction { var, cooldown, .. } if *cooldown <= 0 => {
-                    Some((id.clone(), var.clone()))
-                }
-                _ => None,
+            .map(|id| match self.occupation.get(id) {
+                None => MAX_STOCKPILE,
+                Some(NodeOccupant::Stockpile { var, amt }) if var == v => MAX_STOCKPILE - amt,
+                _ => 0,
             })
-            .collect();
-        const MAX_TURN_ITERS: usize = 10000;
-        for _i in 0..MAX_TURN_ITERS {
-            // select a construction that can produce
-            let can_produce = constructions.iter().enumerate().find(|(_, (id, var))| {
-                let group_id = map.group_from_node(id);
-                let available = map.get_group_bunch(&group_id);
-                let requested = var.request_resources();
-                available.contains(&requested)
-            });
-            let Some((i, (id, var))) = can_produce else {
-                #[cfg(feature = "dbtrace")]
-                println!("production starved: {}", constructions.len());
-                break;
+            .sum()
+    }
+
+    /// remove from the lowest stockpiles first.
+    /// fails without touching the map if the group doesn't have enough.
+    fn take_resource_in_group(
+        &mut self,
+        group_id: &GroupId,
+        v: &ResourceVariant,
+        amt: u32,
+    ) -> Result<StockChanges, MapError> {
+        let available = *self.get_group_bunch(group_id)?.res.get(v).unwrap_or(&0);
+        if available < amt {
+            return Err(MapError::InsufficientResources(v.clone()));
+        }
+        let mut left = amt;
+        let mut actions = vec![];
+        while left > 0 {
+            let lowest_id = self.get_lowest_stockpile(group_id, v)?;
+            let Some(NodeOccupant::Stockpile { amt: stock_amt, .. }) =
+                self.occupation.get_mut(&lowest_id)
+            else {
+                return Err(MapError::UnknownNode(lowest_id));
             };
-            if let Some(NodeOccupant::Construction { cooldown, .. }) = map.occupation.get_mut(id) {
-                *cooldown = var.get_cooldown();
-            }
-            #[cfg(feature = "dbtrace")]
-            println!("producing with {:?} at {:?}", var, id);
-            // delete resources
-            // for every requested resource
-            let group_id = map.group_from_node(id);
-            let requested = var.request_resources();
-            for (var, amt) in requested.res.iter() {
-                let mut left = amt.clone();
-                for _j in 0..MAX_TURN_ITERS {
-                    if left == 0 {
-                        break;
-                    }
-                    // delete from the lowest stockpile
-                    let lowest_id = map.get_lowest_stockpile(&group_id, var);
-                    let NodeOccupant::Stockpile { amt: stock_amt, .. } =
-                        map.occupation.get_mut(&lowest_id).expect("no lowest")
-                    else {
-                        panic!("lowest isn't a stockpile")
-                    };
-                    let clamped = left.min(*stock_amt);
-                    autoactions.actions.push(AutoAction::ConsumeResource {
-                        from: lowest_id.clone(),
-                        to: id.clone(),
-                        var: var.clone(),
-                        abs: *stock_amt - clamped,
-                        diff: *amt as i32,
-                    });
-                    *stock_amt -= clamped;
-                    if *stock_amt == 0 {
-                        map.occupation.remove(&lowest_id);
-                    }
-                    left -= clamped;
-                }
-            }
-            // then add the produced
-            let produced = var.produce_resources();
-            for (var, amt) in produced.res.iter() {
-                if let Ok(actions) = map.add_resource_in_group(&group_id, var, *amt) {
-                    for (to, abs, diff) in actions {
-                        autoactions.actions.push(AutoAction::ProduceResource {
-                            from: id.clone(),
-                            to,
-                            var: var.clone(),
-                            abs,
-                            diff,
-                        });
-                    }
-                }
+            let clamped = left.min(*stock_amt);
+            *stock_amt -= clamped;
+            actions.push((lowest_id.clone(), *stock_amt, clamped as i32));
+            if *stock_amt == 0 {
+                self.occupation.remove(&lowest_id);
             }
-            constructions.remove(i);
+            left -= clamped;
         }
-        // todo:decay
+        Ok(actions)
+    }
 
-        let food = *map
-            .get_group_bunch(&GroupId(0))
-            .res
-            .get(&ResourceVariant::Food)
-            .unwrap_or(&0);
-        let fusion = *map
-            .get_group_bunch(&GroupId(0))
-            .res
-            .get(&ResourceVariant::FusionFuel)
-            .unwrap_or(&0);
+    /// place a new construction on an empty node
+    fn construct(&mut self, id: &NodeId, var: &ConstructionVariant) -> Result<(), MapError> {
+        self.group_from_node(id)?;
+        if self.occupation.get(id).is_some() {
+            return Err(MapError::NoSpace(id.clone()));
+        }
+        self.set_at(
+            id,
+            NodeOccupant::Construction {
+                var: var.clone(),
+                cooldown: 0,
+                tier: 1,
+            },
+        );
+        Ok(())
+    }
 
-        // eat
-        if food > 0 {
-            let lowest_id = map.get_lowest_stockpile(&GroupId(0), &ResourceVariant::Food);
-            let Some(NodeOccupant::Stockpile { amt, .. }) = map.occupation.get_mut(&lowest_id)
-            else {
-                return;
-            };
-            *amt -= 1;
-            autoactions.actions.push(AutoAction::ConsumeResource {
-                from: lowest_id.clone(),
-                to: lowest_id.clone(),
-                var: ResourceVariant::Food,
-                abs: *amt,
-                diff: -1,
-            });
-        } else {
-            next_state.set(AppState::GameOver);
+    /// move a construction to the next tier, returns the new tier
+    fn upgrade(&mut self, id: &NodeId) -> Result<u32, MapError> {
+        let Some(NodeOccupant::Construction { tier, .. }) = self.occupation.get_mut(id) else {
+            return Err(MapError::NoConstruction(id.clone()));
+        };
+        if *tier >= MAX_TIER {
+            return Err(MapError::MaxTier(id.clone()));
         }
+        *tier += 1;
+        Ok(*tier)
+    }
+}
 
-        if let Ok(mut ship) = ship_q.get_single_mut() {
-            if let Some(plan) = ship.planned_move.clone() {
-                if fusion > 0 && plan != ship.orbiting_group {
-                    let lowest_id =
-                        map.get_lowest_stockpile(&GroupId(0), &ResourceVariant::FusionFuel);
-                    let Some(NodeOccupant::Stockpile { amt, .. }) =
-                        map.occupation.get_mut(&lowest_id)
-                    else {
-                        return;
-                    };
-                    *amt -= 1;
-                    autoactions.actions.push(AutoAction::ConsumeResource {
-                        from: lowest_id.clone(),
-                        to: lowest_id.clone(),
-                        var: ResourceVariant::FusionFuel,
-                        abs: *amt,
-                        diff: -1,
-                    });
-                    autoactions.actions.push(AutoAction::ShipMove {
-                        from: ship.orbiting_group.clone(),
-                        to: plan.clone(),
-                    });
-                    // AAAAAAAAAAAAAAH!
-                    unsafe {
-                        // modify the graph to set as adjacent the ship's group
-                        map.edges
-                            .retain(|edge| edge.0 != ship.own_group && edge.1 != ship.own_group);
-                        map.edges.push((ship.own_group.clone(), plan.clone()))
-                    }
-                }
+#[derive(Debug, Clone, PartialEq)]
+enum MapError {
+    UnknownNode(NodeId),
+    UnknownGroup(GroupId),
+    InsufficientResources(ResourceVariant),
+    NoSpace(NodeId),
+    NoConstruction(NodeId),
+    MaxTier(NodeId),
+    NoStockpile(NodeId),
+    OutOfRange(GroupId),
+    Unexplored(GroupId),
+    Scouted(GroupId),
+    NoRoom(ResourceVariant),
+    Away(GroupId),
+    Gone,
+}
+
+impl std::fmt::Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::UnknownNode(id) => trf("error.unknown_node", &[&id.0]),
+            Self::UnknownGroup(id) => trf("error.unknown_group", &[&id.0]),
+            Self::InsufficientResources(var) => {
+                trf("error.insufficient_resources", &[&var.to_string()])
             }
+            Self::NoSpace(id) => trf("error.no_space", &[&id.0]),
+            Self::NoConstruction(id) => trf("error.no_construction", &[&id.0]),
+            Self::MaxTier(id) => trf("error.max_tier", &[&id.0]),
+            Self::NoStockpile(id) => trf("error.no_stockpile", &[&id.0]),
+            Self::OutOfRange(id) => trf("error.out_of_range", &[&id.0]),
+            Self::Unexplored(id) => trf("error.unexplored", &[&id.0]),
+            Self::Scouted(id) => trf("error.scouted", &[&id.0]),
+            Self::NoRoom(var) => trf("error.no_room", &[&var.to_string()]),
+            Self::Away(id) => trf("error.away", &[&id.0]),
+            Self::Gone => tr("error.gone"),
+        };
+        write!(f, "{}", msg)
+    }
+}
 
-            ship.planned_move = None;
-        }
+impl std::error::Error for MapError {}
 
-        // win
-        if fusion > 100 && food > 100 {
-            next_state.set(AppState::GameWon);
-        }
+// events
+#[derive(Event)]
+struct EndTurn;
 
-        // hack to just start the anim
-        autoactions.timer.tick(Duration::from_secs(1));
+/// short message shown on top of the screen
+#[derive(Event)]
+struct Toast {
+    text: String,
+}
 
-        if autoactions.actions.len() > 16 {
-            autoactions.timer.set_duration(Duration::from_millis(100));
-        } else {
-            autoactions.timer.set_duration(Duration::from_millis(300));
+#[derive(Event)]
+struct BuildConstruction {
+    node_id: NodeId,
+    var: ConstructionVariant,
+}
+
+#[derive(Event)]
+struct UpgradeConstruction {
+    node_id: NodeId,
+    tier: u32,
+}
+
+#[derive(Event)]
+struct DestroyConstruction {
+    node_id: NodeId,
+}
+
+#[derive(Event)]
+struct ModifyResource {
+    node_id: NodeId,
+    var: ResourceVariant,
+    abs: u32,
+}
+
+#[derive(Event)]
+struct ModifyResourceFx {
+    from: NodeId,
+    to: NodeId,
+    var: ResourceVariant,
+    diff: i32,
+}
+
+#[derive(Event)]
+enum UiEvent {
+    SelectNodeForConstruction(NodeId),
+    ConstructOnNode(NodeId),
+    SelectNodeForMove(NodeId, bool),
+    ShuttleFrom(NodeId),
+    /// the ship's planet and an unexplored one
+    ScoutPlanet(GroupId, GroupId),
+    Encounter(usize),
+    ToggleStats,
+    ToggleResearch,
+    ToggleOrbits,
+    Close,
+}
+
+fn on_destroy_construction(
+    mut events: EventReader<DestroyConstruction>,
+    mut commands: Commands,
+    query: Query<(Entity, &NodeIdMarker)>,
+) {
+    for event in events.iter() {
+        for (e, marker) in query.iter() {
+            if marker.node_id == event.node_id {
+                commands.entity(e).despawn_recursive();
+            }
         }
     }
 }
 
-fn play_autoactions(
-    mut autoactions: ResMut<AutoActions>,
-    mut event_produce: EventWriter<ModifyResource>,
-    mut fx: EventWriter<ModifyResourceFx>,
-    mut ship_q: Query<(&mut Ship, &mut Visibility, &mut Transform)>,
+fn on_build_construction(
+    mut events: EventReader<BuildConstruction>,
+    mut commands: Commands,
     map: Res<Map>,
-    time: Res<Time>,
+    handles: Res<AssetHandles>,
+) {
+    for event in events.iter() {
+        let pos = map.positions.get(&event.node_id).unwrap().extend(1.);
+        commands.spawn((
+            NodeIdMarker {
+                node_id: event.node_id.clone(),
+            },
+            SpriteSheetBundle {
+                transform: Transform::default().with_translation(pos),
+                sprite: TextureAtlasSprite {
+                    index: event.var.get_sprite_index(),
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            InGame,
+        ));
+    }
+}
+
+/// badge with the tier on the bottom right of the building sprite
+fn on_upgrade_construction(
+    mut events: EventReader<UpgradeConstruction>,
     mut commands: Commands,
+    query: Query<(Entity, &NodeIdMarker)>,
     handles: Res<AssetHandles>,
+    theme: Res<Theme>,
 ) {
-    autoactions.timer.tick(time.delta());
-    if autoactions.timer.finished() {
-        if let Some(act) = &autoactions.current {
-            // sync state at end of actions
-            match &act {
-                AutoAction::ConsumeResource {
-                    from,
-                    to: _,
-                    var,
-                    abs,
-                    diff: _,
-                } => {
-                    event_produce.send(ModifyResource {
-                        node_id: from.clone(),
-                        var: var.clone(),
-                        abs: *abs,
-                    });
-                }
-                AutoAction::ProduceResource {
-                    from: _,
-                    to,
-                    var,
-                    abs,
-                    diff: _,
-                } => {
-                    event_produce.send(ModifyResource {
-                        node_id: to.clone(),
-                        var: var.clone(),
-                        abs: *abs,
+    let text_style = theme.world(theme.normal(&handles));
+    for event in events.iter() {
+        for (e, marker) in query.iter() {
+            if marker.node_id != event.node_id {
+                continue;
+            }
+            commands.entity(e).despawn_descendants();
+            commands.entity(e).with_children(|builder| {
+                builder
+                    .spawn(SpriteSheetBundle {
+                        transform: Transform::default()
+                            .with_translation(Vec3::new(20., -20., 0.1))
+                            .with_scale(Vec3::splat(0.4)),
+                        sprite: TextureAtlasSprite {
+                            index: 1,
+                            ..Default::default()
+                        },
+                        texture_atlas: handles.atlas.clone(),
+                        ..Default::default()
+                    })
+                    .with_children(|badge| {
+                        badge.spawn(Text2dBundle {
+                            text: Text::from_section(format!("{}", event.tier), text_style.clone())
+                                .with_alignment(TextAlignment::Center),
+                            transform: Transform::default()
+                                .with_translation(Vec3::new(0., 0., 0.1))
+                                .with_scale(Vec3::splat(2.5)),
+                            ..default()
+                        });
                     });
-                }
-                AutoAction::ShipMove { from, to } => {
-                    if let Ok((mut ship, mut vis, _)) = ship_q.get_single_mut() {
-                        ship.orbiting_group = to.clone();
-                        *vis = Visibility::Visible;
-                    }
-                }
-            };
-        }
-        if autoactions.actions.is_empty() {
-            autoactions.current = None;
-            return;
+            });
         }
-        let act = autoactions.actions.remove(0);
-
-        let duration = Duration::from_millis(300 as u64);
+    }
+}
 
-        // spawn fx at start of action
-        match &act {
-            AutoAction::ConsumeResource {
-                from,
-                to,
-                var,
-                abs: _,
-                diff,
-            } => {
-                fx.send(ModifyResourceFx {
-                    from: from.clone(),
-                    to: to.clone(),
-                    var: var.clone(),
-                    diff: *diff,
+fn on_modify_resource(
+    mut commands: Commands,
+    mut events: EventReader<ModifyResource>,
+    mut stock_q: Query<(Entity, &NodeIdMarker, &Children)>,
+    mut text_q: Query<&mut Text>,
+    map: Res<Map>,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+) {
+    let text_style = theme.world(theme.big(&handles));
+    let text_alignment = TextAlignment::Center;
+
+    for event in events.iter() {
+        if let Some((ent, _, children)) = stock_q
+            .iter_mut()
+            .find(|(_, marker, _)| marker.node_id == event.node_id)
+        {
+            if event.abs > 0 {
+                let mut text = text_q.get_mut(children[0]).unwrap();
+                text.sections[0].value = format!("{}", event.abs);
+            } else {
+                commands.entity(ent).despawn_recursive();
+            }
+        } else {
+            let pos = map.positions.get(&event.node_id).unwrap().extend(1.);
+            commands
+                .spawn((
+                    NodeIdMarker {
+                        node_id: event.node_id.clone(),
+                    },
+                    SpriteSheetBundle {
+                        transform: Transform::default().with_translation(pos),
+                        sprite: TextureAtlasSprite {
+                            index: event.var.get_sprite_index(),
+                            ..Default::default()
+                        },
+                        texture_atlas: handles.atlas.clone(),
+                        ..Default::default()
+                    },
+                    InGame,
+                ))
+                .with_children(|builder| {
+                    builder.spawn((Text2dBundle {
+                        text: Text::from_section(format!("{}", event.abs), text_style.clone())
+                            .with_alignment(text_alignment),
+                        transform: Transform::default().with_translation(Vec3::new(0., 0., 2.)),
+                        ..default()
+                    },));
+                    builder.spawn(SpriteSheetBundle {
+                        transform: Transform::default().with_translation(Vec3::new(0., 0., 1.8)),
+                        sprite: TextureAtlasSprite {
+                            index: 1,
+                            ..Default::default()
+                        },
+                        texture_atlas: handles.atlas.clone(),
+                        ..Default::default()
+                    });
                 });
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Default)]
+pub struct SpriteInterpolationFx {
+    from: Transform,
+    to: Transform,
+    mid: Option<Transform>,
+    timer: Timer,
+}
+
+fn interpolation_fx(
+    mut commands: Commands,
+    mut fx_query: Query<(Entity, &mut SpriteInterpolationFx, &mut Transform)>,
+    time: Res<Time>,
+    speed: Res<AnimSpeed>,
+) {
+    for (ent, mut fx, mut tr) in fx_query.iter_mut() {
+        fx.timer.tick(time.delta() * speed.factor().unwrap_or(1));
+        if fx.timer.finished() || speed.factor().is_none() {
+            commands.entity(ent).despawn_recursive();
+            continue;
+        }
+        let t = fx.timer.percent();
+        if let Some(mid) = fx.mid {
+            if t < 0.5 {
+                tr.translation = fx.from.translation.lerp(mid.translation, t * 2.0);
+                tr.rotation = fx.from.rotation.lerp(mid.rotation, t * 2.);
+            } else {
+                tr.translation = mid.translation.lerp(fx.to.translation, (t - 0.5) * 2.0);
+                tr.rotation = mid.rotation.lerp(fx.to.rotation, (t - 0.5) * 2.0);
             }
-            AutoAction::ProduceResource {
-                from,
-                to,
-                var,
-                abs: _,
-                diff,
-            } => {
-                fx.send(ModifyResourceFx {
-                    from: from.clone(),
-                    to: to.clone(),
-                    var: var.clone(),
-                    diff: *diff,
+        } else {
+            tr.translation = fx.from.translation.lerp(fx.to.translation, t);
+            tr.rotation = fx.from.rotation.lerp(fx.to.rotation, t);
+        }
+    }
+}
+
+fn on_modify_resource_fx(
+    mut commands: Commands,
+    mut events: EventReader<ModifyResourceFx>,
+    map: Res<Map>,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+) {
+    let text_style = theme.world(theme.big(&handles));
+    let text_alignment = TextAlignment::Center;
+
+    for event in events.iter() {
+        let from = map.positions.get(&event.from).unwrap().extend(1.);
+        let to = map.positions.get(&event.to).unwrap().extend(1.);
+        commands
+            .spawn((
+                SpriteSheetBundle {
+                    transform: Transform::default().with_translation(from),
+                    sprite: TextureAtlasSprite {
+                        index: event.var.get_sprite_index(),
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    ..Default::default()
+                },
+                SpriteInterpolationFx {
+                    from: Transform::default().with_translation(from),
+                    to: Transform::default().with_translation(to),
+                    mid: Some(
+                        Transform::default()
+                            .with_translation((from + to) / 2. + Vec3::new(0., 16., 0.)),
+                    ),
+                    timer: Timer::new(Duration::from_millis(300), TimerMode::Once),
+                },
+                InGame,
+            ))
+            .with_children(|builder| {
+                builder.spawn((Text2dBundle {
+                    text: Text::from_section(format!("{}", event.diff), text_style.clone())
+                        .with_alignment(text_alignment),
+                    transform: Transform::default().with_translation(Vec3::new(0., 0., 2.)),
+                    ..default()
+                },));
+                builder.spawn(SpriteSheetBundle {
+                    transform: Transform::default().with_translation(Vec3::new(0., 0., 1.8)),
+                    sprite: TextureAtlasSprite {
+                        index: 1,
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    ..Default::default()
                 });
+            });
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+struct Node {
+    id: NodeId,
+}
+#[derive(Debug, Clone, Component)]
+struct Planet {
+    id: GroupId,
+}
+#[derive(Debug, Clone, Component)]
+struct Ship {
+    orbiting_group: GroupId,
+    own_group: GroupId,
+    planned_move: Option<GroupId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ResourceVariant {
+    Power,
+    RocketFuel,
+    Food,
+    Material,
+    FusionFuel,
+    Helium3,
+    Helium4,
+    Water,
+    Ore,
+}
+
+impl ResourceVariant {
+    fn get_sprite_index(&self) -> usize {
+        match self {
+            ResourceVariant::Power => 8,
+            ResourceVariant::RocketFuel => 9,
+            ResourceVariant::Food => 10,
+            ResourceVariant::Material => 11,
+            ResourceVariant::FusionFuel => 12,
+            ResourceVariant::Helium3 => 13,
+            ResourceVariant::Helium4 => 14,
+            ResourceVariant::Water => 15,
+            ResourceVariant::Ore => 24,
+        }
+    }
+
+    /// power is used in the turn it's generated instead of filling slots
+    fn is_stockpiled(&self) -> bool {
+        !matches!(self, Self::Power)
+    }
+
+    fn iter() -> impl Iterator<Item = Self> {
+        [
+            Self::Power,
+            Self::RocketFuel,
+            Self::Food,
+            Self::Material,
+            Self::FusionFuel,
+            Self::Helium3,
+            Self::Helium4,
+            Self::Water,
+            Self::Ore,
+        ]
+        .iter()
+        .cloned()
+    }
+}
+
+impl std::fmt::Display for ResourceVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ResourceVariant::Power => tr("resource.power"),
+            ResourceVariant::RocketFuel => tr("resource.rocket_fuel"),
+            ResourceVariant::Food => tr("resource.food"),
+            ResourceVariant::Material => tr("resource.material"),
+            ResourceVariant::FusionFuel => tr("resource.fusion_fuel"),
+            ResourceVariant::Helium3 => tr("resource.helium3"),
+            ResourceVariant::Helium4 => tr("resource.helium4"),
+            ResourceVariant::Water => tr("resource.water"),
+            ResourceVariant::Ore => tr("resource.ore"),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Bunch {
+    res: HashMap<ResourceVariant, u32>,
+}
+
+impl Bunch {
+    fn single(var: ResourceVariant, amt: u32) -> Self {
+        Self {
+            res: HashMap::from([(var, amt)]),
+        }
+    }
+
+    fn many(res: &[(ResourceVariant, u32)]) -> Self {
+        res.iter()
+            .map(|(var, amt)| Self::single(var.clone(), *amt))
+            .sum()
+    }
+
+    fn contains(&self, oth: &Bunch) -> bool {
+        oth.res.iter().all(|(var, amt)| {
+            let Some(cur) = self.res.get(var) else {
+                return false;
+            };
+            cur >= amt
+        })
+    }
+
+    fn describe(&self) -> String {
+        if self.res.is_empty() {
+            return tr("bunch.nothing");
+        }
+        ResourceVariant::iter()
+            .filter_map(|var| {
+                let amt = self.res.get(&var)?;
+                Some(format!("{} {}", amt, var))
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+impl core::ops::Add for Bunch {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut out = self.clone();
+        for (var, amt) in rhs.res.iter() {
+            if let Some(sum) = out.res.get_mut(var) {
+                *sum += amt;
+            } else {
+                out.res.insert(var.clone(), *amt);
             }
-            AutoAction::ShipMove { from, to } => {
-                if let Ok((_ship, mut vis, mut tr)) = ship_q.get_single_mut() {
-                    let from = tr.clone();
+        }
+        out
+    }
+}
+
+impl std::iter::Sum<Self> for Bunch {
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = Self>,
+    {
+        iter.fold(Self::default(), |a, b| a + b)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ConstructionVariant {
+    SolarField,
+    AtmosphereHarvester,
+    ChemicalPlant,
+    PlanetFarm,
+    AsteroidMine,
+    Quarry,
+    PowerPlant,
+    Refinery,
+    Smelter,
+    Lab,
+    Battery,
+}
+
+impl ConstructionVariant {
+    fn get_sprite_index(&self) -> usize {
+        match self {
+            Self::SolarField => 16,
+            Self::AtmosphereHarvester => 17,
+            Self::ChemicalPlant => 18,
+            Self::PlanetFarm => 19,
+            Self::AsteroidMine => 20,
+            Self::Quarry => 21,
+            Self::PowerPlant => 22,
+            Self::Refinery => 23,
+            Self::Smelter => 25,
+            Self::Lab => 26,
+            Self::Battery => 27,
+        }
+    }
+
+    fn get_material_cost(&self) -> u32 {
+        match self {
+            Self::SolarField => 5,
+            Self::AtmosphereHarvester => 20,
+            Self::ChemicalPlant => 3,
+            Self::PlanetFarm => 10,
+            Self::AsteroidMine => 2,
+            Self::Quarry => 5,
+            Self::PowerPlant => 5,
+            Self::Refinery => 10,
+            Self::Smelter => 4,
+            Self::Lab => 6,
+            Self::Battery => 5,
+        }
+    }
+
+    fn request_resources(&self) -> Bunch {
+        match self {
+            Self::SolarField => Bunch::default(),
+            // power doesn't pile up between turns, so the big consumers are
+            // sized to a turn of generation plus what the batteries hold
+            Self::AtmosphereHarvester => Bunch::single(ResourceVariant::Power, 12),
+            Self::ChemicalPlant => Bunch::single(ResourceVariant::Material, 2),
+            Self::PlanetFarm => {
+                Bunch::many(&[(ResourceVariant::Material, 8), (ResourceVariant::Water, 4)])
+            }
+            Self::AsteroidMine => Bunch::single(ResourceVariant::RocketFuel, 2),
+            Self::Quarry => Bunch::single(ResourceVariant::Power, 15),
+            Self::PowerPlant => Bunch::single(ResourceVariant::RocketFuel, 2),
+            Self::Refinery => Bunch::many(&[
+                (ResourceVariant::Helium3, 2),
+                (ResourceVariant::Helium4, 4),
+                (ResourceVariant::Power, 5),
+            ]),
+            Self::Smelter => Bunch::many(&[(ResourceVariant::Ore, 5), (ResourceVariant::Power, 2)]),
+            Self::Lab => {
+                Bunch::many(&[(ResourceVariant::Power, 4), (ResourceVariant::Material, 2)])
+            }
+            Self::Battery => Bunch::default(),
+        }
+    }
+
+    fn produce_resources(&self) -> Bunch {
+        match self {
+            Self::SolarField => Bunch::single(ResourceVariant::Power, 3),
+            Self::AtmosphereHarvester => {
+                Bunch::many(&[(ResourceVariant::Helium3, 4), (ResourceVariant::Helium4, 8)])
+            }
+            Self::ChemicalPlant => Bunch::single(ResourceVariant::RocketFuel, 4),
+            Self::PlanetFarm => Bunch::single(ResourceVariant::Food, 10),
+            Self::AsteroidMine => {
+                Bunch::many(&[(ResourceVariant::Ore, 5), (ResourceVariant::Water, 2)])
+            }
+            Self::Quarry => Bunch::many(&[(ResourceVariant::Ore, 25), (ResourceVariant::Water, 5)]),
+            Self::PowerPlant => Bunch::single(ResourceVariant::Power, 10),
+            Self::Refinery => Bunch::single(ResourceVariant::FusionFuel, 5),
+            Self::Smelter => Bunch::single(ResourceVariant::Material, 5),
+            // labs produce research progress instead
+            Self::Lab => Bunch::default(),
+            // batteries store the power left at the end of the turn
+            Self::Battery => Bunch::default(),
+        }
+    }
+
+    /// output grows by half of the base output every tier
+    fn produce_at(&self, tier: u32) -> Bunch {
+        let mut bunch = self.produce_resources();
+        for amt in bunch.res.values_mut() {
+            *amt += *amt * (tier - 1) / 2;
+        }
+        bunch
+    }
+
+    /// the last tier also shortens the cooldown by a turn
+    fn cooldown_at(&self, tier: u32) -> u32 {
+        if tier >= MAX_TIER {
+            self.get_cooldown().saturating_sub(1).max(1)
+        } else {
+            self.get_cooldown()
+        }
+    }
+
+    /// material needed to go from the tier to the next one
+    fn upgrade_cost(&self, tier: u32) -> u32 {
+        self.get_material_cost() * tier
+    }
+
+    /// buildings that dig their output out of the group's deposits
+    fn is_extractor(&self) -> bool {
+        matches!(
+            self,
+            Self::AtmosphereHarvester | Self::AsteroidMine | Self::Quarry
+        )
+    }
+
+    /// buildings with a lower value get power first
+    fn power_priority(&self) -> u32 {
+        match self {
+            Self::SolarField | Self::PowerPlant => 0,
+            Self::PlanetFarm => 1,
+            Self::AtmosphereHarvester | Self::Refinery => 2,
+            Self::Lab => 4,
+            _ => 3,
+        }
+    }
+
+    fn get_crew(&self) -> u32 {
+        match self {
+            Self::SolarField | Self::PowerPlant | Self::Battery => 0,
+            Self::Quarry => 2,
+            _ => 1,
+        }
+    }
+
+    fn get_cooldown(&self) -> u32 {
+        match self {
+            Self::AtmosphereHarvester => 3,
+            Self::PlanetFarm => 2,
+            Self::Quarry => 3,
+            _ => 1,
+        }
+    }
+
+    fn iter() -> impl Iterator<Item = Self> {
+        [
+            Self::SolarField,
+            Self::AtmosphereHarvester,
+            Self::ChemicalPlant,
+            Self::PlanetFarm,
+            Self::AsteroidMine,
+            Self::Quarry,
+            Self::PowerPlant,
+            Self::Refinery,
+            Self::Smelter,
+            Self::Lab,
+            Self::Battery,
+        ]
+        .iter()
+        .cloned()
+    }
+}
+
+impl std::fmt::Display for ConstructionVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match &self {
+            Self::SolarField => tr("construction.solar_field"),
+            Self::AtmosphereHarvester => tr("construction.atmosphere_harvester"),
+            Self::ChemicalPlant => tr("construction.chemical_plant"),
+            Self::PlanetFarm => tr("construction.planet_farm"),
+            Self::AsteroidMine => tr("construction.asteroid_mine"),
+            Self::Quarry => tr("construction.quarry"),
+            Self::PowerPlant => tr("construction.power_plant"),
+            Self::Refinery => tr("construction.refinery"),
+            Self::Smelter => tr("construction.smelter"),
+            Self::Lab => tr("construction.lab"),
+            Self::Battery => tr("construction.battery"),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+const BATTERY_CAPACITY: u32 = 30;
+
+/// power of a group during the last turn
+#[derive(Debug, Clone, Default)]
+struct PowerBalance {
+    generated: u32,
+    demand: u32,
+    stored: u32,
+    capacity: u32,
+}
+
+/// power left at the end of a turn charges the batteries of its group
+#[derive(Resource, Debug, Clone, Default)]
+struct PowerGrid {
+    stored: HashMap<GroupId, u32>,
+    last_turn: HashMap<GroupId, PowerBalance>,
+}
+
+impl PowerGrid {
+    fn capacity(map: &Map, group_id: &GroupId) -> u32 {
+        map.groups
+            .get(group_id)
+            .map(|nodes| {
+                nodes
+                    .iter()
+                    .filter_map(|id| match map.occupation.get(id) {
+                        Some(NodeOccupant::Construction {
+                            var: ConstructionVariant::Battery,
+                            tier,
+                            ..
+                        }) => Some(BATTERY_CAPACITY * tier),
+                        _ => None,
+                    })
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+}
+
+// shuttles hop between planets, one turn per jump
+const SHUTTLE_RANGE: u32 = 4;
+const SHUTTLE_FUEL_PER_JUMP: u32 = 2;
+
+/// a stockpile flying between two planets
+#[derive(Debug, Clone)]
+struct Shuttle {
+    id: usize,
+    from: GroupId,
+    to: GroupId,
+    var: ResourceVariant,
+    amt: u32,
+    total_turns: u32,
+    turns_left: u32,
+    /// cancelled flights fly back and can't be cancelled again
+    returning: bool,
+}
+
+impl Shuttle {
+    fn progress(&self) -> f32 {
+        1. - self.turns_left as f32 / self.total_turns.max(1) as f32
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+struct Shuttles {
+    flights: Vec<Shuttle>,
+    next_id: usize,
+}
+
+impl Shuttles {
+    /// fuel and turns of a flight, None if the destination is out of range
+    fn plan(map: &Map, from: &GroupId, to: &GroupId) -> Option<(u32, u32)> {
+        let jumps = map.distance(from, to)?;
+        (jumps > 0 && jumps <= SHUTTLE_RANGE).then_some((jumps * SHUTTLE_FUEL_PER_JUMP, jumps))
+    }
+
+    /// load a whole stockpile on a shuttle, the fuel is paid by the planet it leaves
+    fn launch(
+        &mut self,
+        map: &mut Map,
+        node_id: &NodeId,
+        to: &GroupId,
+    ) -> Result<Vec<AutoAction>, MapError> {
+        let from = map.group_from_node(node_id)?;
+        let Some(NodeOccupant::Stockpile { var, amt }) = map.occupation.get(node_id).cloned()
+        else {
+            return Err(MapError::NoStockpile(node_id.clone()));
+        };
+        if !map.is_scouted(to) {
+            return Err(MapError::Unexplored(to.clone()));
+        }
+        let (fuel, turns) =
+            Shuttles::plan(map, &from, to).ok_or(MapError::OutOfRange(to.clone()))?;
+        // the cargo can't pay for its own flight
+        map.occupation.remove(node_id);
+        let paid = match map.take_resource_in_group(&from, &ResourceVariant::RocketFuel, fuel) {
+            Ok(paid) => paid,
+            Err(err) => {
+                map.set_at(node_id, NodeOccupant::Stockpile { var, amt });
+                return Err(err);
+            }
+        };
+        let mut actions: Vec<AutoAction> = paid
+            .into_iter()
+            .map(|(from, abs, diff)| AutoAction::ConsumeResource {
+                from,
+                to: node_id.clone(),
+                var: ResourceVariant::RocketFuel,
+                abs,
+                diff,
+                taken: diff as u32,
+            })
+            .collect();
+        actions.push(AutoAction::ConsumeResource {
+            from: node_id.clone(),
+            to: node_id.clone(),
+            var: var.clone(),
+            abs: 0,
+            diff: -(amt as i32),
+            taken: amt,
+        });
+        self.flights.push(Shuttle {
+            id: self.next_id,
+            from,
+            to: to.clone(),
+            var,
+            amt,
+            total_turns: turns,
+            turns_left: turns,
+            returning: false,
+        });
+        self.next_id += 1;
+        Ok(actions)
+    }
+
+    /// turn a flight around, the fuel is not refunded
+    fn cancel(&mut self, id: usize) {
+        if let Some(flight) = self
+            .flights
+            .iter_mut()
+            .find(|flight| flight.id == id && !flight.returning)
+        {
+            std::mem::swap(&mut flight.from, &mut flight.to);
+            flight.turns_left = flight.total_turns - flight.turns_left;
+            flight.returning = true;
+        }
+    }
+
+    /// advance all flights by a turn and unload the ones that arrived.
+    /// cargo that doesn't fit stays on board, returns how many shuttles are waiting.
+    fn fly(&mut self, map: &mut Map) -> Result<(Vec<AutoAction>, u32), MapError> {
+        let mut actions = vec![];
+        let mut waiting = 0;
+        for flight in self.flights.iter_mut() {
+            flight.turns_left = flight.turns_left.saturating_sub(1);
+            if flight.turns_left > 0 {
+                continue;
+            }
+            let (stored, overflow) =
+                map.add_resource_in_group(&flight.to, &flight.var, flight.amt)?;
+            for (to, abs, diff) in stored {
+                actions.push(AutoAction::ProduceResource {
+                    from: to.clone(),
+                    to,
+                    var: flight.var.clone(),
+                    abs,
+                    diff,
+                });
+            }
+            flight.amt = overflow;
+            if overflow > 0 {
+                waiting += 1;
+            }
+        }
+        self.flights.retain(|flight| flight.amt > 0);
+        Ok((actions, waiting))
+    }
+}
+
+/// splitmix64 so a game is reproducible from its seed, any seed works
+#[derive(Debug, Clone)]
+struct GameRng(u64);
+
+impl GameRng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn range(&mut self, lo: u32, hi: u32) -> u32 {
+        lo + (self.next() % (hi - lo + 1) as u64) as u32
+    }
+
+    fn pick<T: Clone>(&mut self, items: &[T]) -> T {
+        items[self.next() as usize % items.len()].clone()
+    }
+}
+
+// one turn in this many a trader or a wreck shows up, and stays a few turns
+const ENCOUNTER_ODDS: u32 = 4;
+const ENCOUNTER_TURNS: (u32, u32) = (3, 6);
+const TRADER_OFFERS: usize = 3;
+const SALVAGE_FUEL: u32 = 5;
+
+/// what traders ask and what they give back
+const TRADES: &[(ResourceVariant, u32, ResourceVariant, u32)] = &[
+    (ResourceVariant::Food, 10, ResourceVariant::FusionFuel, 4),
+    (ResourceVariant::Material, 10, ResourceVariant::Food, 8),
+    (ResourceVariant::Ore, 20, ResourceVariant::Material, 10),
+    (
+        ResourceVariant::RocketFuel,
+        10,
+        ResourceVariant::Material,
+        8,
+    ),
+    (ResourceVariant::Helium3, 10, ResourceVariant::FusionFuel, 6),
+    (ResourceVariant::Water, 10, ResourceVariant::Food, 5),
+    (
+        ResourceVariant::Material,
+        10,
+        ResourceVariant::RocketFuel,
+        12,
+    ),
+    (ResourceVariant::FusionFuel, 4, ResourceVariant::Food, 12),
+];
+
+#[derive(Debug, Clone)]
+enum EncounterKind {
+    /// indices in TRADES, each offer can be taken once
+    Trader(Vec<usize>),
+    /// material left on board
+    Wreck(u32),
+}
+
+#[derive(Debug, Clone)]
+struct Encounter {
+    id: usize,
+    group: GroupId,
+    kind: EncounterKind,
+    turns_left: u32,
+}
+
+impl Encounter {
+    fn name(&self) -> String {
+        match self.kind {
+            EncounterKind::Trader(_) => tr("encounter.trader"),
+            EncounterKind::Wreck(_) => tr("encounter.wreck"),
+        }
+    }
+
+    fn sprite_index(&self) -> usize {
+        match self.kind {
+            EncounterKind::Trader(_) => 29,
+            EncounterKind::Wreck(_) => 30,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+struct Encounters {
+    rng: GameRng,
+    active: Vec<Encounter>,
+    next_id: usize,
+}
+
+impl Default for Encounters {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Encounters {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: GameRng(seed),
+            active: vec![],
+            next_id: 0,
+        }
+    }
+
+    /// age the encounters by a turn and maybe bring a new one, which is returned
+    fn tick(&mut self, map: &Map) -> Option<Encounter> {
+        for encounter in self.active.iter_mut() {
+            encounter.turns_left = encounter.turns_left.saturating_sub(1);
+        }
+        self.active.retain(|encounter| encounter.turns_left > 0);
+        if self.rng.range(1, ENCOUNTER_ODDS) != 1 {
+            return None;
+        }
+        // one at a time per planet, sorted so the seed decides and not the hash map
+        let mut planets: Vec<GroupId> = map
+            .groups
+            .keys()
+            .filter(|id| id.0 != 0 && !self.active.iter().any(|e| e.group == **id))
+            // nothing shows up where the crew can't see
+            .filter(|id| map.is_scouted(id))
+            .cloned()
+            .collect();
+        if planets.is_empty() {
+            return None;
+        }
+        planets.sort_by_key(|id| id.0);
+        let group = self.rng.pick(&planets);
+        let kind = if self.rng.range(0, 1) == 0 {
+            let mut left: Vec<usize> = (0..TRADES.len()).collect();
+            let mut offers: Vec<usize> = (0..TRADER_OFFERS)
+                .map(|_| left.remove(self.rng.range(0, left.len() as u32 - 1) as usize))
+                .collect();
+            offers.sort();
+            EncounterKind::Trader(offers)
+        } else {
+            EncounterKind::Wreck(self.rng.range(15, 40))
+        };
+        let encounter = Encounter {
+            id: self.next_id,
+            group,
+            kind,
+            turns_left: self.rng.range(ENCOUNTER_TURNS.0, ENCOUNTER_TURNS.1),
+        };
+        self.next_id += 1;
+        self.active.push(encounter.clone());
+        Some(encounter)
+    }
+
+    /// the encounter if the ship orbits its planet
+    fn reach(&mut self, at: &GroupId, id: usize) -> Result<&mut Encounter, MapError> {
+        let encounter = self
+            .active
+            .iter_mut()
+            .find(|encounter| encounter.id == id)
+            .ok_or(MapError::Gone)?;
+        if encounter.group != *at {
+            return Err(MapError::Away(encounter.group.clone()));
+        }
+        Ok(encounter)
+    }
+
+    /// take an offer with the ship's hold, a trader with nothing left leaves
+    fn trade(
+        &mut self,
+        map: &mut Map,
+        at: &GroupId,
+        id: usize,
+        offer: usize,
+    ) -> Result<Vec<AutoAction>, MapError> {
+        let EncounterKind::Trader(offers) = &mut self.reach(at, id)?.kind else {
+            return Err(MapError::Gone);
+        };
+        let i = offers
+            .iter()
+            .position(|o| *o == offer)
+            .ok_or(MapError::Gone)?;
+        let (give, give_amt, get, get_amt) = &TRADES[offer];
+        let actions = barter(map, (give, *give_amt), (get, *get_amt))?;
+        offers.remove(i);
+        self.active.retain(|encounter| {
+            !matches!(&encounter.kind, EncounterKind::Trader(offers) if offers.is_empty())
+        });
+        Ok(actions)
+    }
+
+    /// strip a wreck of its material, the shuttles burn rocket fuel
+    fn salvage(
+        &mut self,
+        map: &mut Map,
+        at: &GroupId,
+        id: usize,
+    ) -> Result<Vec<AutoAction>, MapError> {
+        let EncounterKind::Wreck(material) = self.reach(at, id)?.kind else {
+            return Err(MapError::Gone);
+        };
+        let actions = barter(
+            map,
+            (&ResourceVariant::RocketFuel, SALVAGE_FUEL),
+            (&ResourceVariant::Material, material),
+        )?;
+        self.active.retain(|encounter| encounter.id != id);
+        Ok(actions)
+    }
+}
+
+/// pay from the ship's hold and load what comes back, nothing changes if it doesn't fit
+fn barter(
+    map: &mut Map,
+    pay: (&ResourceVariant, u32),
+    receive: (&ResourceVariant, u32),
+) -> Result<Vec<AutoAction>, MapError> {
+    let hold = GroupId(0);
+    let paid = map.take_resource_in_group(&hold, pay.0, pay.1)?;
+    if map.room_for(&hold, receive.0) < receive.1 {
+        map.add_resource_in_group(&hold, pay.0, pay.1)?;
+        return Err(MapError::NoRoom(receive.0.clone()));
+    }
+    let (stored, _) = map.add_resource_in_group(&hold, receive.0, receive.1)?;
+    let mut actions: Vec<AutoAction> = paid
+        .into_iter()
+        .map(|(from, abs, diff)| AutoAction::ConsumeResource {
+            from: from.clone(),
+            to: from,
+            var: pay.0.clone(),
+            abs,
+            diff: -diff,
+            taken: diff as u32,
+        })
+        .collect();
+    actions.extend(
+        stored
+            .into_iter()
+            .map(|(to, abs, diff)| AutoAction::ProduceResource {
+                from: to.clone(),
+                to,
+                var: receive.0.clone(),
+                abs,
+                diff,
+            }),
+    );
+    Ok(actions)
+}
+
+/// the variant whose debug name is the word, as written in the data files
+fn variant_named<T: std::fmt::Debug>(
+    mut variants: impl Iterator<Item = T>,
+    name: &str,
+) -> Option<T> {
+    variants.find(|var| format!("{:?}", var) == name)
+}
+
+/// id from the tech file, like `deep_mining`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct TechId(&'static str);
+
+#[derive(Debug, Clone)]
+enum TechEffect {
+    Unlock(ConstructionVariant),
+    /// turns removed from the cooldown
+    Cooldown(ConstructionVariant, u32),
+    /// amount removed from one of the requested resources
+    Cheaper(ConstructionVariant, ResourceVariant, u32),
+}
+
+impl TechEffect {
+    /// `unlock Quarry`, `cooldown AtmosphereHarvester 1` or `cheaper PlanetFarm Water 2`
+    fn parse(text: &str) -> Option<Self> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let var = variant_named(ConstructionVariant::iter(), words.get(1)?)?;
+        Some(match words[0] {
+            "unlock" => Self::Unlock(var),
+            "cooldown" => Self::Cooldown(var, words.get(2)?.parse().ok()?),
+            "cheaper" => Self::Cheaper(
+                var,
+                variant_named(ResourceVariant::iter(), words.get(2)?)?,
+                words.get(3)?.parse().ok()?,
+            ),
+            _ => return None,
+        })
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Unlock(var) => trf("tech.unlock", &[&var.to_string()]),
+            Self::Cooldown(var, turns) => trf("tech.cooldown", &[&var.to_string(), turns]),
+            Self::Cheaper(var, res, amt) => {
+                trf("tech.cheaper", &[&var.to_string(), amt, &res.to_string()])
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Tech {
+    id: TechId,
+    /// message id of the name
+    name: String,
+    /// lab turns needed
+    cost: u32,
+    requires: Vec<TechId>,
+    effect: TechEffect,
+}
+
+static TECHS: OnceLock<Vec<Tech>> = OnceLock::new();
+
+/// `id = cost | requires | effect` lines, a tech can only require the ones above it
+fn parse_techs(source: &'static str) -> Vec<Tech> {
+    let mut techs: Vec<Tech> = vec![];
+    for line in source
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+    {
+        let tech = line.split_once('=').and_then(|(id, value)| {
+            let mut fields = value.split('|');
+            let id = TechId(id.trim());
+            let cost = fields.next()?.trim().parse().ok()?;
+            let requires = fields
+                .next()?
+                .split_whitespace()
+                .map(|req| {
+                    techs
+                        .iter()
+                        .find(|tech| tech.id.0 == req)
+                        .map(|tech| tech.id)
+                })
+                .collect::<Option<Vec<TechId>>>()?;
+            Some(Tech {
+                id,
+                name: format!("tech.{}", id.0),
+                cost,
+                requires,
+                effect: TechEffect::parse(fields.next()?)?,
+            })
+        });
+        match tech {
+            Some(tech) => techs.push(tech),
+            None => warn!("bad tech: {}", line),
+        }
+    }
+    techs
+}
+
+fn techs() -> &'static [Tech] {
+    TECHS.get_or_init(|| parse_techs(include_str!("../assets/techs.txt")))
+}
+
+fn tech(id: TechId) -> &'static Tech {
+    techs()
+        .iter()
+        .find(|tech| tech.id == id)
+        .expect("every tech id is in the tech file")
+}
+
+/// the id as written in a saved file, unknown ones are dropped
+fn tech_named(name: &str) -> Option<TechId> {
+    techs()
+        .iter()
+        .find(|tech| tech.id.0 == name)
+        .map(|tech| tech.id)
+}
+
+/// research progress of the current game
+#[derive(Resource, Debug, Clone, Default)]
+struct Research {
+    done: Vec<TechId>,
+    current: Option<TechId>,
+    /// lab turns spent on each tech, kept when switching
+    progress: HashMap<TechId, u32>,
+}
+
+impl Research {
+    /// `tech = id`, `researching = id` and `progress = id turns` lines, other keys are skipped
+    fn parse(text: &str) -> Self {
+        let mut research = Self::default();
+        for (key, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            let mut words = value.split_whitespace();
+            let Some(id) = words.next().and_then(tech_named) else {
+                continue;
+            };
+            match key.trim() {
+                "tech" => research.done.push(id),
+                "researching" => research.current = Some(id),
+                "progress" => {
+                    if let Some(turns) = words.next().and_then(|n| n.parse().ok()) {
+                        research.progress.insert(id, turns);
+                    }
+                }
+                _ => {}
+            }
+        }
+        research
+    }
+
+    fn to_text(&self) -> String {
+        let done = self.done.iter().map(|id| format!("tech = {}\n", id.0));
+        let current = self
+            .current
+            .iter()
+            .map(|id| format!("researching = {}\n", id.0));
+        // in file order so the save doesn't shuffle
+        let progress = techs().iter().filter_map(|tech| {
+            let turns = self.progress.get(&tech.id)?;
+            Some(format!("progress = {} {}\n", tech.id.0, turns))
+        });
+        done.chain(current).chain(progress).collect()
+    }
+
+    fn available(&self, tech: &Tech) -> bool {
+        !self.done.contains(&tech.id) && tech.requires.iter().all(|id| self.done.contains(id))
+    }
+
+    fn effects(&self) -> impl Iterator<Item = &'static TechEffect> + '_ {
+        self.done.iter().map(|id| &tech(*id).effect)
+    }
+
+    /// constructions unlocked by a tech are hidden until it's researched
+    fn unlocked(&self, var: &ConstructionVariant) -> bool {
+        techs().iter().all(|tech| {
+            !matches!(&tech.effect, TechEffect::Unlock(v) if v == var)
+                || self.done.contains(&tech.id)
+        })
+    }
+
+    fn cooldown(&self, var: &ConstructionVariant, tier: u32) -> u32 {
+        let saved: u32 = self
+            .effects()
+            .filter_map(|effect| match effect {
+                TechEffect::Cooldown(v, turns) if v == var => Some(*turns),
+                _ => None,
+            })
+            .sum();
+        var.cooldown_at(tier).saturating_sub(saved).max(1)
+    }
+
+    fn request(&self, var: &ConstructionVariant) -> Bunch {
+        let mut bunch = var.request_resources();
+        for effect in self.effects() {
+            if let TechEffect::Cheaper(v, res, amt) = effect {
+                if v == var {
+                    if let Some(cur) = bunch.res.get_mut(res) {
+                        *cur = cur.saturating_sub(*amt);
+                    }
+                }
+            }
+        }
+        bunch.res.retain(|_, amt| *amt > 0);
+        bunch
+    }
+
+    fn pick(&mut self, id: TechId) {
+        if self.available(tech(id)) {
+            self.current = Some(id);
+        }
+    }
+
+    /// lab turns on the current tech, returns it once completed
+    fn work(&mut self, turns: u32) -> Option<TechId> {
+        let id = self.current?;
+        let progress = self.progress.entry(id).or_insert(0);
+        *progress += turns;
+        if *progress < tech(id).cost {
+            return None;
+        }
+        self.done.push(id);
+        self.current = None;
+        Some(id)
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+struct NodeIdMarker {
+    node_id: NodeId,
+}
+
+fn setup_scene(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    mut map: ResMut<Map>,
+    mut event_construct: EventWriter<BuildConstruction>,
+    mut event_produce: EventWriter<ModifyResource>,
+    mut next_state: ResMut<NextState<AppState>>,
+    scenario: Res<Scenario>,
+) {
+    commands.spawn((
+        SpriteBundle {
+            texture: handles.ship.clone(),
+            transform: Transform::default().with_translation(Vec3::new(-80., -360. + 128., 0.0)),
+            ..Default::default()
+        },
+        InGame,
+    ));
+
+    // planets and nodes
+    for (id, pos) in map.group_positions.iter() {
+        if id == &GroupId(0) {
+            // that's a ship not a planet
+            continue;
+        }
+        let kind = map.kinds.get(id).copied().unwrap_or(BodyKind::Rocky);
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default()
+                    .with_translation(pos.extend(-0.05))
+                    .with_scale(Vec3::splat(kind.sprite_scale())),
+                sprite: TextureAtlasSprite {
+                    index: 3,
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            Planet { id: id.clone() },
+            InGame,
+        ));
+    }
+    for (id, pos) in map.positions.iter() {
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default().with_translation(pos.extend(0.0)),
+                sprite: TextureAtlasSprite {
+                    index: 0,
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            Node { id: id.clone() },
+            InGame,
+        ));
+    }
+
+    // map
+    commands.spawn((
+        SpriteBundle {
+            texture: handles.map.clone(),
+            transform: Transform::default().with_translation(Vec3::new(0., 0., -0.1)),
+            ..Default::default()
+        },
+        InGame,
+    ));
+
+    //ship
+    let group_pos = map.group_positions[&GroupId(1)].extend(0.2);
+    commands.spawn((
+        SpriteSheetBundle {
+            transform: Transform::default()
+                .with_translation(group_pos + Vec3::new(32., 0., 0.))
+                .with_rotation(Quat::from_rotation_z(PI / 2.)),
+            sprite: TextureAtlasSprite {
+                index: 4,
+                ..Default::default()
+            },
+            texture_atlas: handles.atlas.clone(),
+            ..Default::default()
+        },
+        Ship {
+            own_group: GroupId(0),
+            orbiting_group: GroupId(1),
+            planned_move: None,
+        },
+        InGame,
+    ));
+
+    let occ = NodeOccupant::Construction {
+        var: ConstructionVariant::SolarField,
+        cooldown: 0,
+        tier: 1,
+    };
+    map.set_at(&NodeId(0), occ);
+    event_construct.send(BuildConstruction {
+        node_id: NodeId(0),
+        var: ConstructionVariant::SolarField,
+    });
+
+    // what doesn't fit in the hold is left behind
+    for var in ResourceVariant::iter() {
+        let Some(amt) = scenario.start.res.get(&var) else {
+            continue;
+        };
+        if let Ok((actions, _)) = map.add_resource_in_group(&GroupId(0), &var, *amt) {
+            for (to, abs, _diff) in actions {
+                event_produce.send(ModifyResource {
+                    node_id: to.clone(),
+                    var: var.clone(),
+                    abs,
+                });
+            }
+        }
+    }
+
+    next_state.set(AppState::Gameplay);
+}
+
+/// planets, their nodes and what sits on them follow the orbits
+#[allow(clippy::type_complexity)]
+fn follow_orbits(
+    map: Res<Map>,
+    mut query_planets: Query<(&mut Transform, &Planet)>,
+    mut query_nodes: Query<(&mut Transform, &Node), Without<Planet>>,
+    mut query_markers: Query<(&mut Transform, &NodeIdMarker), (Without<Planet>, Without<Node>)>,
+    mut query_ship: Query<
+        (&mut Transform, &Ship),
+        (Without<Planet>, Without<Node>, Without<NodeIdMarker>),
+    >,
+) {
+    if !map.is_changed() {
+        return;
+    }
+    let mut moved = HashMap::new();
+    for (mut tr, planet) in query_planets.iter_mut() {
+        let Some(pos) = map.group_positions.get(&planet.id) else {
+            continue;
+        };
+        moved.insert(planet.id.clone(), *pos - tr.translation.truncate());
+        tr.translation = pos.extend(tr.translation.z);
+    }
+    for (mut tr, node) in query_nodes.iter_mut() {
+        if let Some(pos) = map.positions.get(&node.id) {
+            tr.translation = pos.extend(tr.translation.z);
+        }
+    }
+    for (mut tr, marker) in query_markers.iter_mut() {
+        if let Some(pos) = map.positions.get(&marker.node_id) {
+            tr.translation = pos.extend(tr.translation.z);
+        }
+    }
+    if let Ok((mut tr, ship)) = query_ship.get_single_mut() {
+        if let Some(delta) = moved.get(&ship.orbiting_group) {
+            tr.translation += delta.extend(0.);
+        }
+    }
+}
+
+fn ship_orbit(mut query_ship: Query<(&mut Transform, &Ship)>, map: Res<Map>, time: Res<Time>) {
+    if let Ok((mut tr, ship)) = query_ship.get_single_mut() {
+        let group_pos = map.group_positions.get(&ship.orbiting_group).unwrap();
+        tr.rotate_around(
+            group_pos.extend(0.),
+            Quat::from_rotation_z(0.1 * time.delta_seconds()),
+        );
+    }
+}
+
+#[derive(Component)]
+struct UiShipPlanMarker;
+
+fn ship_plan(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    query_m: Query<(Entity, &UiShipPlanMarker)>,
+    query_ship: Query<&Ship>,
+    map: Res<Map>,
+) {
+    for (e, _) in query_m.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    if let Ok(ship) = query_ship.get_single() {
+        if let Some(plan) = &ship.planned_move {
+            let group_pos = map.group_positions.get(plan).unwrap();
+            commands.spawn((
+                SpriteSheetBundle {
+                    transform: Transform::default().with_translation(group_pos.extend(0.1)),
+                    sprite: TextureAtlasSprite {
+                        color: theme.negative,
+                        index: 5,
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    ..Default::default()
+                },
+                UiShipPlanMarker,
+                InGame,
+            ));
+            // ghost of the ship, so that the plan isn't told only by the colour
+            commands.spawn((
+                SpriteSheetBundle {
+                    transform: Transform::default()
+                        .with_translation(group_pos.extend(0.15) + Vec3::new(32., 0., 0.))
+                        .with_rotation(Quat::from_rotation_z(PI / 2.)),
+                    sprite: TextureAtlasSprite {
+                        color: Color::WHITE.with_a(0.5),
+                        index: 4,
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    ..Default::default()
+                },
+                UiShipPlanMarker,
+            ));
+        }
+    }
+}
+
+fn send_end_turn(keys: Res<Input<KeyCode>>, mut events: EventWriter<EndTurn>) {
+    if keys.just_pressed(KeyCode::Delete) {
+        events.send(EndTurn);
+    }
+}
+
+fn stats_hotkey(keys: Res<Input<KeyCode>>, mut event_ui: EventWriter<UiEvent>) {
+    if keys.just_pressed(KeyCode::T) {
+        event_ui.send(UiEvent::ToggleStats);
+    }
+}
+
+fn orbits_hotkey(keys: Res<Input<KeyCode>>, mut event_ui: EventWriter<UiEvent>) {
+    if keys.just_pressed(KeyCode::O) {
+        event_ui.send(UiEvent::ToggleOrbits);
+    }
+}
+
+fn research_hotkey(keys: Res<Input<KeyCode>>, mut event_ui: EventWriter<UiEvent>) {
+    if keys.just_pressed(KeyCode::R) {
+        event_ui.send(UiEvent::ToggleResearch);
+    }
+}
+
+fn speed_hotkey(
+    keys: Res<Input<KeyCode>>,
+    mut speed: ResMut<AnimSpeed>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if keys.just_pressed(KeyCode::F) {
+        *speed = speed.next();
+        toasts.send(Toast {
+            text: trf("speed.set", &[&speed.to_string()]),
+        });
+    }
+}
+
+/// how fast the end of a turn plays
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum AnimSpeed {
+    #[default]
+    Normal,
+    Double,
+    Quadruple,
+    Instant,
+}
+
+impl AnimSpeed {
+    fn next(&self) -> Self {
+        match self {
+            Self::Normal => Self::Double,
+            Self::Double => Self::Quadruple,
+            Self::Quadruple => Self::Instant,
+            Self::Instant => Self::Normal,
+        }
+    }
+
+    /// None when nothing is animated
+    fn factor(&self) -> Option<u32> {
+        match self {
+            Self::Normal => Some(1),
+            Self::Double => Some(2),
+            Self::Quadruple => Some(4),
+            Self::Instant => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AnimSpeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.factor() {
+            Some(factor) => write!(f, "{}x", factor),
+            None => write!(f, "{}", tr("speed.instant")),
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+struct AutoActions {
+    actions: Vec<AutoAction>,
+    current: Option<AutoAction>,
+    timer: Timer,
+    /// end turns asked for while the last one was still playing
+    pending_turns: u32,
+}
+
+impl Default for AutoActions {
+    fn default() -> Self {
+        Self {
+            actions: vec![],
+            current: None,
+            timer: Timer::new(Duration::from_millis(300), TimerMode::Repeating),
+            pending_turns: 0,
+        }
+    }
+}
+
+impl AutoActions {
+    fn done(&self) -> bool {
+        self.actions.is_empty() && self.current.is_none()
+    }
+
+    /// play the first action right away, orders given during the animation don't hurry it
+    fn start(&mut self) {
+        if self.current.is_none() {
+            self.timer.tick(Duration::from_secs(1));
+        }
+    }
+
+    /// everything left to play, in order, to be applied without animation
+    fn skip(&mut self) -> Vec<AutoAction> {
+        self.current
+            .take()
+            .into_iter()
+            .chain(self.actions.drain(..))
+            .collect()
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+enum AutoAction {
+    ConsumeResource {
+        from: NodeId,
+        to: NodeId,
+        var: ResourceVariant,
+        abs: u32,
+        diff: i32,
+        /// what left this stockpile, diff is what was asked for
+        taken: u32,
+    },
+    ProduceResource {
+        from: NodeId,
+        to: NodeId,
+        var: ResourceVariant,
+        abs: u32,
+        diff: i32,
+    },
+    ShipMove {
+        to: GroupId,
+    },
+}
+
+#[derive(Resource, Debug, Clone)]
+struct TurnCount {
+    count: u32,
+}
+
+impl Default for TurnCount {
+    fn default() -> Self {
+        Self { count: 1 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct TurnStats {
+    turn: u32,
+    totals: HashMap<GroupId, Bunch>,
+    produced: HashMap<ConstructionVariant, Bunch>,
+    consumed: HashMap<ConstructionVariant, Bunch>,
+    wasted: Bunch,
+}
+
+/// per turn history, used for balancing
+#[derive(Resource, Debug, Clone, Default)]
+struct Stats {
+    turns: Vec<TurnStats>,
+    /// buildings bought by the player, the ship's own don't count
+    built: HashMap<ConstructionVariant, u32>,
+}
+
+impl Stats {
+    fn record(&mut self, turn: u32, map: &Map, actions: &[AutoAction], wasted: Bunch) {
+        let mut stats = TurnStats {
+            turn,
+            totals: map
+                .groups
+                .keys()
+                .map(|group_id| {
+                    (
+                        group_id.clone(),
+                        map.get_group_bunch(group_id).unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            wasted,
+            ..Default::default()
+        };
+        for act in actions {
+            let (building, var, amt, table) = match act {
+                AutoAction::ProduceResource {
+                    from, var, diff, ..
+                } => (from, var, *diff as u32, &mut stats.produced),
+                AutoAction::ConsumeResource { to, var, taken, .. } => {
+                    (to, var, *taken, &mut stats.consumed)
+                }
+                AutoAction::ShipMove { .. } => continue,
+            };
+            let Some(NodeOccupant::Construction { var: constr, .. }) = map.occupation.get(building)
+            else {
+                continue;
+            };
+            let entry = table.entry(constr.clone()).or_default();
+            *entry = entry.clone() + Bunch::single(var.clone(), amt);
+        }
+        self.turns.push(stats);
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = "turn,kind,key,resource,amount\n".to_string();
+        for stats in self.turns.iter() {
+            let mut rows: Vec<(&str, String, &Bunch)> = vec![];
+            for (group_id, bunch) in stats.totals.iter() {
+                rows.push(("total", format!("group{}", group_id.0), bunch));
+            }
+            for (constr, bunch) in stats.produced.iter() {
+                rows.push(("produced", constr.to_string(), bunch));
+            }
+            for (constr, bunch) in stats.consumed.iter() {
+                rows.push(("consumed", constr.to_string(), bunch));
+            }
+            rows.push(("wasted", "".to_string(), &stats.wasted));
+            for (kind, key, bunch) in rows {
+                for (var, amt) in bunch.res.iter() {
+                    csv += &format!("{},{},{},{},{}\n", stats.turn, kind, key, var, amt);
+                }
+            }
+        }
+        csv
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Goal {
+    Win,
+    /// win by this turn
+    WinWithin(u32),
+    /// win without ever building it
+    WinWithout(ConstructionVariant),
+    /// win without losing a single stockpile to full slots
+    WinWithoutWaste,
+    /// end a turn with exactly this much food on the ship
+    FoodLeft(u32),
+}
+
+impl Goal {
+    fn reached(&self, turn: u32, map: &Map, stats: &Stats, won: bool) -> bool {
+        match self {
+            Self::Win => won,
+            Self::WinWithin(turns) => won && turn <= *turns,
+            Self::WinWithout(var) => won && !stats.built.contains_key(var),
+            Self::WinWithoutWaste => {
+                won && stats.turns.iter().all(|turn_stats| {
+                    turn_stats
+                        .wasted
+                        .res
+                        .iter()
+                        .all(|(var, amt)| !var.is_stockpiled() || *amt == 0)
+                })
+            }
+            Self::FoodLeft(food) => {
+                let ship = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+                ship.res.get(&ResourceVariant::Food).unwrap_or(&0) == food
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Achievement {
+    /// saved in the profile
+    id: &'static str,
+    /// message ids of the name and of what to do
+    name: &'static str,
+    desc: &'static str,
+    goal: Goal,
+}
+
+const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "win",
+        name: "achievement.win",
+        desc: "achievement.win_desc",
+        goal: Goal::Win,
+    },
+    Achievement {
+        id: "swift",
+        name: "achievement.swift",
+        desc: "achievement.swift_desc",
+        goal: Goal::WinWithin(40),
+    },
+    Achievement {
+        id: "no_quarry",
+        name: "achievement.no_quarry",
+        desc: "achievement.no_quarry_desc",
+        goal: Goal::WinWithout(ConstructionVariant::Quarry),
+    },
+    Achievement {
+        id: "no_waste",
+        name: "achievement.no_waste",
+        desc: "achievement.no_waste_desc",
+        goal: Goal::WinWithoutWaste,
+    },
+    Achievement {
+        id: "last_crumbs",
+        name: "achievement.last_crumbs",
+        desc: "achievement.last_crumbs_desc",
+        goal: Goal::FoodLeft(1),
+    },
+];
+
+const PROFILE_FILE: &str = "profile.txt";
+const LEADERBOARD_FILE: &str = "leaderboard.txt";
+
+/// read a file next to the game, the browser build has none
+#[cfg(not(target_arch = "wasm32"))]
+fn read_local(path: &str) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_local(_path: &str) -> Option<String> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_local(path: &str, text: &str) {
+    if let Err(err) = std::fs::write(path, text) {
+        warn!("failed to save {}: {}", path, err);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_local(_path: &str, _text: &str) {}
+
+/// what is kept between games, one `key = value` per line like the language files
+#[derive(Resource, Debug, Clone, Default)]
+struct Profile {
+    achievements: Vec<String>,
+}
+
+impl Profile {
+    fn parse(text: &str) -> Self {
+        let mut profile = Self::default();
+        for (key, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            if key.trim() == "achievement" {
+                profile.achievements.push(value.trim().to_string());
+            }
+        }
+        profile
+    }
+
+    fn to_text(&self) -> String {
+        self.achievements
+            .iter()
+            .map(|id| format!("achievement = {}\n", id))
+            .collect()
+    }
+
+    fn load() -> Self {
+        read_local(PROFILE_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        write_local(PROFILE_FILE, &self.to_text());
+    }
+
+    fn unlocked(&self, id: &str) -> bool {
+        self.achievements.iter().any(|a| a == id)
+    }
+
+    /// true only the first time
+    fn unlock(&mut self, id: &str) -> bool {
+        if self.unlocked(id) {
+            return false;
+        }
+        self.achievements.push(id.to_string());
+        true
+    }
+}
+
+/// unlock the achievements whose goal is reached, toast and save the new ones
+fn award(profile: &mut Profile, toasts: &mut EventWriter<Toast>, reached: impl Fn(&Goal) -> bool) {
+    let mut new = false;
+    for achievement in ACHIEVEMENTS.iter().filter(|a| reached(&a.goal)) {
+        if profile.unlock(achievement.id) {
+            toasts.send(Toast {
+                text: trf("achievement.unlocked", &[&tr(achievement.name)]),
+            });
+            new = true;
+        }
+    }
+    if new {
+        profile.save();
+    }
+}
+
+fn check_achievements(
+    turns: Res<TurnCount>,
+    map: Res<Map>,
+    stats: Res<Stats>,
+    crew: Res<Crew>,
+    mut profile: ResMut<Profile>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !turns.is_changed() || turns.count == 0 || crew.awake == 0 {
+        return;
+    }
+    award(&mut profile, &mut toasts, |goal| {
+        goal.reached(turns.count, &map, &stats, false)
+    });
+}
+
+fn win_achievements(
+    turns: Res<TurnCount>,
+    map: Res<Map>,
+    stats: Res<Stats>,
+    mut profile: ResMut<Profile>,
+    mut toasts: EventWriter<Toast>,
+) {
+    award(&mut profile, &mut toasts, |goal| {
+        goal.reached(turns.count, &map, &stats, true)
+    });
+}
+
+// every turn under par is worth points, then stockpiles and building tiers
+const PAR_TURNS: u32 = 60;
+const SCORE_PER_TURN: u32 = 20;
+const SCORE_PER_TIER: u32 = 10;
+const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Score {
+    speed: u32,
+    /// every stockpiled unit left on the map
+    surplus: u32,
+    buildings: u32,
+}
+
+impl Score {
+    fn new(map: &Map, turns: u32) -> Self {
+        let mut surplus = 0;
+        let mut buildings = 0;
+        for occ in map.occupation.values() {
+            match occ {
+                NodeOccupant::Stockpile { amt, .. } => surplus += amt,
+                NodeOccupant::Construction { tier, .. } => buildings += tier * SCORE_PER_TIER,
+            }
+        }
+        Self {
+            speed: PAR_TURNS.saturating_sub(turns) * SCORE_PER_TURN,
+            surplus,
+            buildings,
+        }
+    }
+
+    fn total(&self) -> u32 {
+        self.speed + self.surplus + self.buildings
+    }
+}
+
+/// best scores, one `map = score` per line
+#[derive(Resource, Debug, Clone, Default)]
+struct Leaderboard {
+    entries: Vec<(String, u32)>,
+}
+
+impl Leaderboard {
+    fn parse(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let (map, score) = line.split_once('=')?;
+                Some((map.trim().to_string(), score.trim().parse().ok()?))
+            })
+            .collect();
+        let mut board = Self { entries };
+        board.trim();
+        board
+    }
+
+    fn to_text(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(map, score)| format!("{} = {}\n", map, score))
+            .collect()
+    }
+
+    fn load() -> Self {
+        read_local(LEADERBOARD_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        write_local(LEADERBOARD_FILE, &self.to_text());
+    }
+
+    /// only the best scores of every map are kept, so the file stays small
+    fn trim(&mut self) {
+        self.entries
+            .sort_by(|(map_a, a), (map_b, b)| map_a.cmp(map_b).then(b.cmp(a)));
+        let mut kept: HashMap<String, usize> = HashMap::new();
+        self.entries.retain(|(map, _)| {
+            let count = kept.entry(map.clone()).or_default();
+            *count += 1;
+            *count <= LEADERBOARD_SIZE
+        });
+    }
+
+    /// scores of a map, best first
+    fn top(&self, map: &str) -> Vec<u32> {
+        self.entries
+            .iter()
+            .filter(|(m, _)| m == map)
+            .map(|(_, score)| *score)
+            .collect()
+    }
+
+    /// add a score and return its rank from 1, None if it didn't make the board
+    fn submit(&mut self, map: &str, score: u32) -> Option<usize> {
+        let rank = self
+            .top(map)
+            .iter()
+            .take_while(|best| **best >= score)
+            .count();
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+        self.entries.push((map.to_string(), score));
+        self.trim();
+        Some(rank + 1)
+    }
+}
+
+/// change of the ship inventory during the last turn
+#[derive(Resource, Debug, Clone, Default)]
+struct ShipNet {
+    net: HashMap<ResourceVariant, i32>,
+}
+
+/// consume the inputs of a construction and store what it produces
+fn produce(
+    map: &mut Map,
+    research: &Research,
+    id: &NodeId,
+    var: &ConstructionVariant,
+    power: &mut HashMap<GroupId, u32>,
+    actions: &mut Vec<AutoAction>,
+    wasted: &mut Bunch,
+) -> Result<u32, MapError> {
+    let group_id = map.group_from_node(id)?;
+    let Some(NodeOccupant::Construction { tier, .. }) = map.occupation.get(id).cloned() else {
+        return Err(MapError::NoConstruction(id.clone()));
+    };
+    let pool = power.entry(group_id.clone()).or_insert(0);
+    let mut generated = 0;
+    for (res, amt) in research.request(var).res.iter() {
+        if !res.is_stockpiled() {
+            if *pool < *amt {
+                return Err(MapError::InsufficientResources(res.clone()));
+            }
+            *pool -= amt;
+            actions.push(AutoAction::ConsumeResource {
+                from: id.clone(),
+                to: id.clone(),
+                var: res.clone(),
+                abs: *pool,
+                diff: *amt as i32,
+                taken: *amt,
+            });
+            continue;
+        }
+        for (from, abs, diff) in map.take_resource_in_group(&group_id, res, *amt)? {
+            actions.push(AutoAction::ConsumeResource {
+                from,
+                to: id.clone(),
+                var: res.clone(),
+                abs,
+                diff: *amt as i32,
+                taken: diff as u32,
+            });
+        }
+    }
+    for (res, amt) in map.extract(&group_id, var, tier).res.iter() {
+        if !res.is_stockpiled() {
+            *pool += amt;
+            generated += amt;
+            actions.push(AutoAction::ProduceResource {
+                from: id.clone(),
+                to: id.clone(),
+                var: res.clone(),
+                abs: *pool,
+                diff: *amt as i32,
+            });
+            continue;
+        }
+        let (stored, overflow) = map.add_resource_in_group(&group_id, res, *amt)?;
+        *wasted = wasted.clone() + Bunch::single(res.clone(), overflow);
+        for (to, abs, diff) in stored {
+            actions.push(AutoAction::ProduceResource {
+                from: id.clone(),
+                to,
+                var: res.clone(),
+                abs,
+                diff,
+            });
+        }
+    }
+    Ok(generated)
+}
+
+/// runs every building off cooldown, power goes to the lowest priority first
+/// and what's left charges the batteries. returns the waste and the toasts
+fn run_constructions(
+    map: &mut Map,
+    research: &mut Research,
+    grid: &mut PowerGrid,
+    mut staff: u32,
+    ship_at: &GroupId,
+    actions: &mut Vec<AutoAction>,
+) -> (Bunch, Vec<String>) {
+    let mut messages = vec![];
+    let mut wasted = Bunch::default();
+    let mut constructions: Vec<(NodeId, ConstructionVariant, u32)> = map
+        .occupation
+        .iter()
+        .filter_map(|(id, occ)| match occ {
+            NodeOccupant::Construction {
+                var,
+                cooldown,
+                tier,
+            } if *cooldown == 0 => Some((id.clone(), var.clone(), *tier)),
+            _ => None,
+        })
+        .collect();
+    map.fired.clear();
+    // batteries are drained into the turn's power and refilled at the end
+    let mut power = grid.stored.clone();
+    let mut balance: HashMap<GroupId, PowerBalance> = HashMap::new();
+    for (id, var, _) in constructions.iter() {
+        if let Ok(group_id) = map.group_from_node(id) {
+            balance.entry(group_id).or_default().demand += *research
+                .request(var)
+                .res
+                .get(&ResourceVariant::Power)
+                .unwrap_or(&0);
+        }
+    }
+    constructions.sort_by_key(|(_, var, _)| var.power_priority());
+    const MAX_TURN_ITERS: usize = 10000;
+    for _i in 0..MAX_TURN_ITERS {
+        // select a construction that can produce
+        let can_produce = constructions.iter().enumerate().find(|(_, (id, var, _))| {
+            let Ok(group_id) = map.group_from_node(id) else {
+                return false;
+            };
+            if !Crew::can_staff(staff, var, &group_id, ship_at) {
+                return false;
+            }
+            if *var == ConstructionVariant::Lab && research.current.is_none() {
+                return false;
+            }
+            if !map.can_extract(&group_id, var) {
+                return false;
+            }
+            let Ok(available) = map.get_group_bunch(&group_id) else {
+                return false;
+            };
+            let mut requested = research.request(var);
+            let needed = requested.res.remove(&ResourceVariant::Power).unwrap_or(0);
+            available.contains(&requested) && *power.get(&group_id).unwrap_or(&0) >= needed
+        });
+        let Some((i, (id, var, tier))) = can_produce else {
+            #[cfg(feature = "dbtrace")]
+            println!("production starved: {}", constructions.len());
+            break;
+        };
+        if let Some(NodeOccupant::Construction { cooldown, .. }) = map.occupation.get_mut(id) {
+            *cooldown = research.cooldown(var, *tier);
+        }
+        #[cfg(feature = "dbtrace")]
+        println!("producing with {:?} at {:?}", var, id);
+        staff -= var.get_crew();
+        match produce(map, research, id, var, &mut power, actions, &mut wasted) {
+            Ok(generated) => {
+                map.fired.push(id.clone());
+                if let Ok(group_id) = map.group_from_node(id) {
+                    balance.entry(group_id).or_default().generated += generated;
+                }
+                if *var == ConstructionVariant::Lab {
+                    if let Some(done) = research.work(*tier) {
+                        messages.push(trf("research.done", &[&tr(&tech(done).name)]));
+                    }
+                }
+            }
+            Err(err) => messages.push(err.to_string()),
+        }
+        constructions.remove(i);
+    }
+    // todo:decay
+
+    for group_id in map.groups.keys() {
+        let capacity = PowerGrid::capacity(map, group_id);
+        let left = *power.get(group_id).unwrap_or(&0);
+        let stored = left.min(capacity);
+        if left > stored {
+            wasted = wasted + Bunch::single(ResourceVariant::Power, left - stored);
+        }
+        grid.stored.insert(group_id.clone(), stored);
+        let entry = balance.entry(group_id.clone()).or_default();
+        entry.stored = stored;
+        entry.capacity = capacity;
+    }
+    grid.last_turn = balance;
+    (wasted, messages)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn turn(
+    mut events: EventReader<EndTurn>,
+    mut map: ResMut<Map>,
+    mut autoactions: ResMut<AutoActions>,
+    mut ship_q: Query<&mut Ship>,
+    mut turns: ResMut<TurnCount>,
+    mut ship_net: ResMut<ShipNet>,
+    mut stats: ResMut<Stats>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut crew: ResMut<Crew>,
+    mut research: ResMut<Research>,
+    mut grid: ResMut<PowerGrid>,
+    mut shuttles: ResMut<Shuttles>,
+    mut encounters: ResMut<Encounters>,
+    mut scenario: ResMut<Scenario>,
+    mut toasts: EventWriter<Toast>,
+) {
+    // end turns asked for during the animation wait for it
+    autoactions.pending_turns += events.iter().count() as u32;
+    while autoactions.pending_turns > 0 && autoactions.done() {
+        autoactions.pending_turns -= 1;
+        turns.count += 1;
+        let ship_before = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+        for (_id, occ) in map.occupation.iter_mut() {
+            match occ {
+                NodeOccupant::Construction { cooldown, .. } if *cooldown > 0 => {
+                    *cooldown -= 1;
+                }
+                _ => {}
+            }
+        }
+        match shuttles.fly(&mut map) {
+            Ok((delivered, waiting)) => {
+                autoactions.actions.extend(delivered);
+                if waiting > 0 {
+                    toasts.send(Toast {
+                        text: trf("shuttle.waiting", &[&waiting]),
+                    });
+                }
+            }
+            Err(err) => toasts.send(Toast {
+                text: err.to_string(),
+            }),
+        }
+        if let Some(encounter) = encounters.tick(&map) {
+            toasts.send(Toast {
+                text: trf(
+                    "encounter.arrived",
+                    &[&encounter.name(), &encounter.group.0, &encounter.turns_left],
+                ),
+            });
+        }
+        let actions_start = autoactions.actions.len();
+        let ship_at = ship_q
+            .get_single()
+            .map_or(GroupId(0), |ship| ship.orbiting_group.clone());
+        let (wasted, messages) = run_constructions(
+            &mut map,
+            &mut research,
+            &mut grid,
+            crew.awake,
+            &ship_at,
+            &mut autoactions.actions,
+        );
+        for text in messages {
+            toasts.send(Toast { text });
+        }
+
+        stats.record(
+            turns.count,
+            &map,
+            &autoactions.actions[actions_start..],
+            wasted,
+        );
+
+        let ship = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+        let food = *ship.res.get(&ResourceVariant::Food).unwrap_or(&0);
+        let fusion = *ship.res.get(&ResourceVariant::FusionFuel).unwrap_or(&0);
+
+        // eat
+        let needed = crew.food_needed();
+        let eaten = food.min(needed);
+        if eaten > 0 {
+            match map.take_resource_in_group(&GroupId(0), &ResourceVariant::Food, eaten) {
+                Ok(actions) => {
+                    for (from, abs, diff) in actions {
+                        autoactions.actions.push(AutoAction::ConsumeResource {
+                            from: from.clone(),
+                            to: from,
+                            var: ResourceVariant::Food,
+                            abs,
+                            diff: -diff,
+                            taken: diff as u32,
+                        });
+                    }
+                }
+                Err(err) => toasts.send(Toast {
+                    text: err.to_string(),
+                }),
+            }
+        }
+        // starving crew members die one per turn
+        if eaten < needed {
+            let woken = crew.starve();
+            toasts.send(Toast {
+                text: tr("crew.starved"),
+            });
+            if woken {
+                toasts.send(Toast {
+                    text: tr("crew.woken"),
+                });
+            }
+        }
+
+        if let Ok(mut ship) = ship_q.get_single_mut() {
+            if let Some(plan) = ship.planned_move.clone() {
+                let cost = map.jump_cost(&ship.orbiting_group, &plan);
+                if fusion >= cost && plan != ship.orbiting_group {
+                    match map.take_resource_in_group(
+                        &GroupId(0),
+                        &ResourceVariant::FusionFuel,
+                        cost,
+                    ) {
+                        Ok(actions) => {
+                            for (from, abs, diff) in actions {
+                                autoactions.actions.push(AutoAction::ConsumeResource {
+                                    from: from.clone(),
+                                    to: from,
+                                    var: ResourceVariant::FusionFuel,
+                                    abs,
+                                    diff: -diff,
+                                    taken: diff as u32,
+                                });
+                            }
+                            autoactions
+                                .actions
+                                .push(AutoAction::ShipMove { to: plan.clone() });
+                            // modify the graph to set as adjacent the ship's group
+                            map.edges.retain(|edge| {
+                                edge.0 != ship.own_group && edge.1 != ship.own_group
+                            });
+                            map.dock(&ship.own_group, &plan);
+                            scenario.visit(&plan);
+                            if map.scout(&plan) {
+                                toasts.send(Toast {
+                                    text: map.scout_report(&plan),
+                                });
+                            }
+                        }
+                        Err(err) => toasts.send(Toast {
+                            text: err.to_string(),
+                        }),
+                    }
+                }
+            }
+
+            ship.planned_move = None;
+        }
+        // the windows the player planned with close only after the moves
+        map.update_orbits(turns.count);
+
+        let ship_after = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+        ship_net.net = ResourceVariant::iter()
+            .map(|var| {
+                let before = *ship_before.res.get(&var).unwrap_or(&0) as i32;
+                let after = *ship_after.res.get(&var).unwrap_or(&0) as i32;
+                (var, after - before)
+            })
+            .collect();
+
+        match scenario.outcome(turns.count, &map, &crew) {
+            Some(true) => next_state.set(AppState::GameWon),
+            Some(false) => next_state.set(AppState::GameOver),
+            None => {}
+        }
+
+        autoactions.start();
+
+        if autoactions.actions.len() > 16 {
+            autoactions.timer.set_duration(Duration::from_millis(100));
+        } else {
+            autoactions.timer.set_duration(Duration::from_millis(300));
+        }
+    }
+}
+
+/// sync state at end of actions
+fn finish_autoaction(
+    act: &AutoAction,
+    map: &Map,
+    event_produce: &mut EventWriter<ModifyResource>,
+    ship_q: &mut Query<(&mut Ship, &mut Visibility, &mut Transform)>,
+) {
+    match act {
+        AutoAction::ConsumeResource {
+            from,
+            to: _,
+            var,
+            abs,
+            diff: _,
+            taken: _,
+        } if var.is_stockpiled() => {
+            event_produce.send(ModifyResource {
+                node_id: from.clone(),
+                var: var.clone(),
+                abs: *abs,
+            });
+        }
+        AutoAction::ProduceResource {
+            from: _,
+            to,
+            var,
+            abs,
+            diff: _,
+        } if var.is_stockpiled() => {
+            event_produce.send(ModifyResource {
+                node_id: to.clone(),
+                var: var.clone(),
+                abs: *abs,
+            });
+        }
+        // power is spent within the turn, there's no stockpile to sync
+        AutoAction::ConsumeResource { .. } | AutoAction::ProduceResource { .. } => {}
+        AutoAction::ShipMove { to } => {
+            if let Ok((mut ship, mut vis, mut tr)) = ship_q.get_single_mut() {
+                ship.orbiting_group = to.clone();
+                // a skipped move never got to place the ship
+                tr.translation =
+                    map.group_positions.get(to).unwrap().extend(0.2) + Vec3::new(32., 0., 0.);
+                tr.rotation = Quat::from_rotation_z(PI / 2.);
+                *vis = Visibility::Visible;
+            }
+        }
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_autoactions(
+    mut autoactions: ResMut<AutoActions>,
+    mut event_produce: EventWriter<ModifyResource>,
+    mut fx: EventWriter<ModifyResourceFx>,
+    mut ship_q: Query<(&mut Ship, &mut Visibility, &mut Transform)>,
+    map: Res<Map>,
+    time: Res<Time>,
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    speed: Res<AnimSpeed>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let Some(factor) = speed
+        .factor()
+        .filter(|_| !keys.just_pressed(KeyCode::Space))
+    else {
+        for act in autoactions.skip() {
+            finish_autoaction(&act, &map, &mut event_produce, &mut ship_q);
+        }
+        return;
+    };
+    autoactions.timer.tick(time.delta() * factor);
+    if autoactions.timer.finished() {
+        if let Some(act) = &autoactions.current {
+            finish_autoaction(act, &map, &mut event_produce, &mut ship_q);
+        }
+        if autoactions.actions.is_empty() {
+            autoactions.current = None;
+            return;
+        }
+        let act = autoactions.actions.remove(0);
+
+        let duration = Duration::from_millis(300);
+
+        // spawn fx at start of action
+        match &act {
+            AutoAction::ConsumeResource {
+                from,
+                to,
+                var,
+                abs: _,
+                diff,
+                taken: _,
+            } => {
+                fx.send(ModifyResourceFx {
+                    from: from.clone(),
+                    to: to.clone(),
+                    var: var.clone(),
+                    diff: *diff,
+                });
+            }
+            AutoAction::ProduceResource {
+                from,
+                to,
+                var,
+                abs: _,
+                diff,
+            } => {
+                fx.send(ModifyResourceFx {
+                    from: from.clone(),
+                    to: to.clone(),
+                    var: var.clone(),
+                    diff: *diff,
+                });
+            }
+            AutoAction::ShipMove { to } => {
+                if let Ok((_ship, mut vis, mut tr)) = ship_q.get_single_mut() {
+                    let from = *tr;
                     tr.translation =
                         map.group_positions.get(to).unwrap().extend(0.2) + Vec3::new(32., 0., 0.);
                     tr.rotation = Quat::from_rotation_z(PI / 2.);
-                    let to = tr.clone();
+                    let to = *tr;
                     *vis = Visibility::Hidden;
                     commands.spawn((
                         SpriteSheetBundle {
@@ -1363,1185 +4366,4311 @@ fn play_autoactions(
                             texture_atlas: handles.atlas.clone(),
                             ..Default::default()
                         },
-                        SpriteInterpolationFx {
-                            from: from,
-                            to: to,
-                            mid: None,
-                            timer: Timer::new(duration, TimerMode::Once),
+                        SpriteInterpolationFx {
+                            from,
+                            to,
+                            mid: None,
+                            timer: Timer::new(duration, TimerMode::Once),
+                        },
+                        InGame,
+                    ));
+                }
+            }
+        };
+        autoactions.current = Some(act);
+    }
+}
+
+#[derive(Clone, Debug, Component)]
+struct Highlight;
+#[derive(Clone, Debug, Component)]
+struct Selected;
+#[derive(Clone, Debug, Component)]
+struct SelectedMove;
+
+#[allow(clippy::too_many_arguments)]
+fn highlight(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    query_highlight: Query<(Entity, &Highlight)>,
+    query_nodes: Query<(Entity, &Node)>,
+    query_planets: Query<(Entity, &Planet)>,
+    query_tr: Query<&Transform>,
+    query_windows: Query<&Window, With<PrimaryWindow>>,
+    query_camera: Query<(&Camera, &GlobalTransform)>,
+    mut event_ui: EventWriter<UiEvent>,
+    input: (Res<Input<MouseButton>>, Res<NavCursor>),
+    query_moving_to: Query<&MovingTo>,
+    query_move_ship: Query<(Entity, &UiSelectedMoveShip)>,
+    mut map: ResMut<Map>,
+    mut ship_q: Query<&mut Ship>,
+    mut autoactions: ResMut<AutoActions>,
+) {
+    for (e, _) in query_move_ship.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for (e, _) in query_highlight.iter() {
+        commands.entity(e).despawn();
+    }
+
+    let Ok(ship) = ship_q.get_single() else {
+        return;
+    };
+
+    let orbiting = ship.orbiting_group.clone();
+    let star = map.star(&orbiting);
+    let mut nears = vec![];
+    nears.push(orbiting.clone());
+    for neighbor_group_id in star.iter() {
+        if neighbor_group_id == &GroupId(0) {
+            // that's a ship not a planet
+            continue;
+        }
+        nears.push(neighbor_group_id.clone());
+        let pos = map.group_positions.get(neighbor_group_id).unwrap();
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default().with_translation(pos.extend(0.)),
+                sprite: TextureAtlasSprite {
+                    index: 5,
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            UiSelectedMoveShip,
+            InGame,
+        ));
+    }
+
+    let (mouse_button_input, nav) = input;
+    let (clicked, mouse) = match nav.world_position(&map) {
+        Some(pos) if nav.active => (nav.confirm, pos),
+        _ => {
+            let clicked = mouse_button_input.just_pressed(MouseButton::Left);
+
+            let Some(mouse_viewport) = query_windows.single().cursor_position() else {
+                return;
+            };
+
+            let (camera, camera_transform) = query_camera.single();
+            let mouse = camera
+                .viewport_to_world_2d(camera_transform, mouse_viewport)
+                .unwrap_or(Vec2::ZERO);
+            (clicked, mouse)
+        }
+    };
+
+    let mut found_node = None;
+    for (ent, node) in query_nodes.iter() {
+        let tr = query_tr.get(ent).unwrap();
+        let rect = Rect::from_center_size(
+            Vec2::new(tr.translation.x, tr.translation.y),
+            Vec2::new(64., 64.),
+        );
+        if rect.contains(mouse) && map.is_node_scouted(&node.id) {
+            found_node = Some((tr, node));
+        }
+    }
+
+    if let Some((tr, node)) = found_node {
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default()
+                    .with_translation(tr.translation + Vec3::new(0., 0., 3.)),
+                sprite: TextureAtlasSprite {
+                    color: Color::WHITE.with_a(0.3),
+                    index: 2,
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            Highlight,
+            InGame,
+        ));
+        if clicked {
+            if let Ok(MovingTo(from_id, nears, split)) = query_moving_to.get_single() {
+                if nears.contains(&node.id) && node.id != *from_id {
+                    let Some(NodeOccupant::Stockpile {
+                        var: from_var,
+                        amt: from_amt_full,
+                    }) = map.occupation.get(from_id).cloned()
+                    else {
+                        return;
+                    };
+
+                    let from_amt = if *split {
+                        from_amt_full / 2
+                    } else {
+                        from_amt_full
+                    };
+
+                    if let Some(NodeOccupant::Stockpile {
+                        var: to_var,
+                        amt: to_amt,
+                    }) = map.occupation.get(&node.id)
+                    {
+                        if *to_var == from_var {
+                            let clamped = (from_amt + to_amt).min(100);
+                            if from_amt == from_amt_full {
+                                map.occupation.remove(from_id);
+                                autoactions.actions.push(AutoAction::ConsumeResource {
+                                    from: from_id.clone(),
+                                    to: node.id.clone(),
+                                    var: from_var.clone(),
+                                    abs: 0,
+                                    diff: from_amt as i32,
+                                    taken: from_amt,
+                                });
+                            } else {
+                                map.occupation.insert(
+                                    from_id.clone(),
+                                    NodeOccupant::Stockpile {
+                                        var: from_var.clone(),
+                                        amt: from_amt_full - from_amt,
+                                    },
+                                );
+                                autoactions.actions.push(AutoAction::ConsumeResource {
+                                    from: from_id.clone(),
+                                    to: node.id.clone(),
+                                    var: from_var.clone(),
+                                    abs: from_amt_full - from_amt,
+                                    diff: from_amt as i32,
+                                    taken: from_amt,
+                                });
+                            }
+                            map.occupation.insert(
+                                node.id.clone(),
+                                NodeOccupant::Stockpile {
+                                    var: from_var.clone(),
+                                    amt: clamped,
+                                },
+                            );
+                            autoactions.actions.push(AutoAction::ProduceResource {
+                                from: node.id.clone(),
+                                to: node.id.clone(),
+                                var: from_var.clone(),
+                                abs: clamped,
+                                diff: from_amt as i32,
+                            });
+                        }
+                    } else if map.occupation.get(&node.id).is_none() {
+                        if from_amt == from_amt_full {
+                            map.occupation.remove(from_id);
+                            autoactions.actions.push(AutoAction::ConsumeResource {
+                                from: from_id.clone(),
+                                to: node.id.clone(),
+                                var: from_var.clone(),
+                                abs: 0,
+                                diff: from_amt as i32,
+                                taken: from_amt,
+                            });
+                        } else {
+                            map.occupation.insert(
+                                from_id.clone(),
+                                NodeOccupant::Stockpile {
+                                    var: from_var.clone(),
+                                    amt: from_amt_full - from_amt,
+                                },
+                            );
+                            autoactions.actions.push(AutoAction::ConsumeResource {
+                                from: from_id.clone(),
+                                to: node.id.clone(),
+                                var: from_var.clone(),
+                                abs: from_amt_full - from_amt,
+                                diff: from_amt as i32,
+                                taken: from_amt,
+                            });
+                        }
+                        map.occupation.insert(
+                            node.id.clone(),
+                            NodeOccupant::Stockpile {
+                                var: from_var.clone(),
+                                amt: from_amt,
+                            },
+                        );
+                        autoactions.actions.push(AutoAction::ProduceResource {
+                            from: node.id.clone(),
+                            to: node.id.clone(),
+                            var: from_var.clone(),
+                            abs: from_amt,
+                            diff: from_amt as i32,
+                        });
+                        autoactions.start();
+                        event_ui.send(UiEvent::Close);
+                        return;
+                    }
+                }
+            }
+            event_ui.send(UiEvent::SelectNodeForConstruction(node.id.clone()));
+        }
+    }
+
+    let mut found_planet = None;
+    for (ent, node) in query_planets.iter() {
+        let tr = query_tr.get(ent).unwrap();
+        let rect = Rect::from_center_size(
+            Vec2::new(tr.translation.x, tr.translation.y),
+            Vec2::new(64., 64.),
+        );
+        if rect.contains(mouse) {
+            found_planet = Some((tr, node));
+        }
+    }
+
+    if let Some((tr, planet)) = found_planet {
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default()
+                    .with_translation(tr.translation + Vec3::new(0., 0., 3.)),
+                sprite: TextureAtlasSprite {
+                    color: Color::WHITE.with_a(0.3),
+                    index: 5,
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            Highlight,
+            InGame,
+        ));
+        if clicked && !map.is_scouted(&planet.id) {
+            event_ui.send(UiEvent::ScoutPlanet(orbiting.clone(), planet.id.clone()));
+        }
+        if clicked {
+            let fusion = *map
+                .get_group_bunch(&GroupId(0))
+                .unwrap_or_default()
+                .res
+                .get(&ResourceVariant::FusionFuel)
+                .unwrap_or(&0);
+            if nears.contains(&planet.id) && fusion >= map.jump_cost(&orbiting, &planet.id) {
+                if let Ok(mut ship) = ship_q.get_single_mut() {
+                    ship.planned_move = Some(planet.id.clone());
+                    event_ui.send(UiEvent::Close);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum NavTarget {
+    Node(NodeId),
+    Planet(GroupId),
+}
+
+/// keyboard and gamepad focus, used in place of the mouse while active
+#[derive(Resource, Clone, Debug, Default)]
+struct NavCursor {
+    active: bool,
+    target: Option<NavTarget>,
+    confirm: bool,
+    in_panel: bool,
+    button: Option<Entity>,
+}
+
+impl NavCursor {
+    fn world_position(&self, map: &Map) -> Option<Vec2> {
+        match &self.target {
+            Some(NavTarget::Node(id)) => map.positions.get(id).cloned(),
+            Some(NavTarget::Planet(id)) => map.group_positions.get(id).cloned(),
+            None => None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct NavMarker;
+
+#[derive(Default)]
+struct NavInput {
+    dir: Option<Vec2>,
+    confirm: bool,
+    cancel: bool,
+    toggle: bool,
+    end_turn: bool,
+}
+
+fn read_nav_input(
+    keys: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    pad_buttons: &Input<GamepadButton>,
+) -> NavInput {
+    let mut input = NavInput::default();
+    let dirs = [
+        (KeyCode::Up, GamepadButtonType::DPadUp, Vec2::Y),
+        (KeyCode::Down, GamepadButtonType::DPadDown, Vec2::NEG_Y),
+        (KeyCode::Left, GamepadButtonType::DPadLeft, Vec2::NEG_X),
+        (KeyCode::Right, GamepadButtonType::DPadRight, Vec2::X),
+    ];
+    let pad = |button: GamepadButtonType| {
+        gamepads
+            .iter()
+            .any(|gamepad| pad_buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+    for (key, button, dir) in dirs {
+        if keys.just_pressed(key) || pad(button) {
+            input.dir = Some(dir);
+        }
+    }
+    input.confirm = keys.just_pressed(KeyCode::Return) || pad(GamepadButtonType::South);
+    input.cancel = keys.just_pressed(KeyCode::Back) || pad(GamepadButtonType::East);
+    input.toggle = keys.just_pressed(KeyCode::Tab) || pad(GamepadButtonType::North);
+    input.end_turn = pad(GamepadButtonType::Start);
+    input
+}
+
+#[allow(clippy::too_many_arguments)]
+fn navigation(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut nav: ResMut<NavCursor>,
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    pad_buttons: Res<Input<GamepadButton>>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut event_ui: EventWriter<UiEvent>,
+    mut events_end: EventWriter<EndTurn>,
+    query_marker: Query<Entity, With<NavMarker>>,
+    mut query_buttons: Query<(Entity, &mut Interaction, &GlobalTransform), With<UiButton>>,
+    ship_q: Query<&Ship>,
+    map: Res<Map>,
+) {
+    for e in query_marker.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    nav.confirm = false;
+
+    let input = read_nav_input(&keys, &gamepads, &pad_buttons);
+    if cursor_moved.iter().count() > 0 {
+        nav.active = false;
+    }
+    if input.dir.is_some() || input.confirm || input.cancel || input.toggle {
+        nav.active = true;
+    }
+    if input.end_turn {
+        events_end.send(EndTurn);
+    }
+    if !nav.active {
+        return;
+    }
+    if input.cancel {
+        event_ui.send(UiEvent::Close);
+        nav.in_panel = false;
+    }
+
+    // buttons, in reading order
+    let mut buttons: Vec<(Entity, Vec3)> = query_buttons
+        .iter()
+        .map(|(e, _, tr)| (e, tr.translation()))
+        .collect();
+    buttons.sort_by(|a, b| (a.1.y, a.1.x).partial_cmp(&(b.1.y, b.1.x)).unwrap());
+    if input.toggle {
+        nav.in_panel = !nav.in_panel;
+    }
+    if buttons.is_empty() {
+        nav.in_panel = false;
+    }
+
+    if nav.in_panel {
+        let current = buttons.iter().position(|(e, _)| Some(*e) == nav.button);
+        let index = match (current, input.dir) {
+            (Some(i), Some(dir)) if dir.y < 0. || dir.x > 0. => (i + 1) % buttons.len(),
+            (Some(i), Some(_)) => (i + buttons.len() - 1) % buttons.len(),
+            (Some(i), None) => i,
+            (None, _) => 0,
+        };
+        let focused = buttons[index].0;
+        nav.button = Some(focused);
+        if let Ok((_, mut interaction, _)) = query_buttons.get_mut(focused) {
+            if input.confirm {
+                *interaction = Interaction::Pressed;
+            } else {
+                interaction.set_if_neq(Interaction::Hovered);
+            }
+        }
+        return;
+    }
+    nav.button = None;
+
+    // snap to the closest node or planet in the pressed direction
+    let mut targets: Vec<(NavTarget, Vec2)> = map
+        .positions
+        .iter()
+        .filter(|(id, _)| map.is_node_scouted(id))
+        .map(|(id, pos)| (NavTarget::Node(id.clone()), *pos))
+        .collect();
+    targets.extend(
+        map.group_positions
+            .iter()
+            .filter(|(id, _)| **id != GroupId(0))
+            .map(|(id, pos)| (NavTarget::Planet(id.clone()), *pos)),
+    );
+    match (nav.world_position(&map), input.dir) {
+        (None, _) => {
+            let orbit = ship_q
+                .get_single()
+                .map(|ship| ship.orbiting_group.clone())
+                .unwrap_or(GroupId(1));
+            nav.target = Some(NavTarget::Planet(orbit));
+        }
+        (Some(from), Some(dir)) => {
+            let next = targets
+                .into_iter()
+                .filter(|(_, pos)| (*pos - from).dot(dir) > 1.)
+                .min_by(|a, b| {
+                    let score = |pos: Vec2| {
+                        let d = pos - from;
+                        d.dot(dir) + 2. * d.perp_dot(dir).abs()
+                    };
+                    score(a.1).partial_cmp(&score(b.1)).unwrap()
+                });
+            if let Some((target, _)) = next {
+                nav.target = Some(target);
+            }
+        }
+        _ => {}
+    }
+    nav.confirm = input.confirm;
+
+    let marker = match &nav.target {
+        Some(NavTarget::Node(_)) => 2,
+        _ => 5,
+    };
+    if let Some(pos) = nav.world_position(&map) {
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default().with_translation(pos.extend(6.)),
+                sprite: TextureAtlasSprite {
+                    color: theme.focus,
+                    index: marker,
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            NavMarker,
+        ));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum HoverTarget {
+    Node(NodeId),
+    Planet(GroupId),
+    Construct(NodeId, ConstructionVariant),
+}
+
+#[derive(Resource, Clone, Debug)]
+struct Hover {
+    target: Option<HoverTarget>,
+    timer: Timer,
+}
+
+impl Default for Hover {
+    fn default() -> Self {
+        Self {
+            target: None,
+            timer: Timer::new(Duration::from_millis(500), TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Component)]
+struct UiTooltip;
+
+fn tooltip_text(
+    target: &HoverTarget,
+    map: &Map,
+    research: &Research,
+    ship: Option<&Ship>,
+) -> String {
+    match target {
+        HoverTarget::Node(node_id) => match map.occupation.get(node_id) {
+            Some(NodeOccupant::Construction {
+                var,
+                cooldown,
+                tier,
+            }) => trf(
+                "tooltip.building",
+                &[
+                    &var.to_string(),
+                    tier,
+                    &research.request(var).describe(),
+                    &map.node_output(node_id, var, *tier).describe(),
+                    &var.get_crew(),
+                    &research.cooldown(var, *tier),
+                    cooldown,
+                    &if map.fired.contains(node_id) {
+                        tr("tooltip.fired")
+                    } else {
+                        tr("tooltip.not_fired")
+                    },
+                ],
+            ),
+            Some(NodeOccupant::Stockpile { var, amt }) => trf(
+                "tooltip.stockpile",
+                &[&var.to_string(), amt, &MAX_STOCKPILE],
+            ),
+            None => tr("tooltip.empty"),
+        },
+        HoverTarget::Planet(group_id) => {
+            let slots = map.groups.get(group_id).map(|g| g.len()).unwrap_or(0);
+            let buildings: Vec<String> = map
+                .groups
+                .get(group_id)
+                .into_iter()
+                .flatten()
+                .filter_map(|node_id| match map.occupation.get(node_id) {
+                    Some(NodeOccupant::Construction { var, .. }) => Some(var.to_string()),
+                    _ => None,
+                })
+                .collect();
+            let free = map
+                .groups
+                .get(group_id)
+                .into_iter()
+                .flatten()
+                .filter(|node_id| map.occupation.get(*node_id).is_none())
+                .count();
+            let travel = match ship {
+                Some(ship) if ship.orbiting_group == *group_id => tr("tooltip.orbiting"),
+                Some(ship) => match map.distance(&ship.orbiting_group, group_id) {
+                    Some(1) => trf(
+                        "tooltip.jump",
+                        &[&map.jump_cost(&ship.orbiting_group, group_id)],
+                    ),
+                    Some(jumps) => trf("tooltip.jumps", &[&jumps]),
+                    None => tr("tooltip.unreachable"),
+                },
+                None => "".to_string(),
+            };
+            if !map.is_scouted(group_id) {
+                return trf("tooltip.unexplored", &[&travel]);
+            }
+            trf(
+                "tooltip.planet",
+                &[
+                    &map.kinds
+                        .get(group_id)
+                        .map_or(tr("tooltip.none"), |kind| kind.to_string()),
+                    &slots,
+                    &free,
+                    &if buildings.is_empty() {
+                        tr("tooltip.none")
+                    } else {
+                        buildings.join(", ")
+                    },
+                    &map.reserves(group_id).describe(),
+                    &((map.irradiance(group_id) * 100.).round() as u32),
+                    &travel,
+                ],
+            )
+        }
+        HoverTarget::Construct(node_id, var) => trf(
+            "tooltip.construct",
+            &[
+                &var.to_string(),
+                &var.get_material_cost(),
+                &ResourceVariant::Material.to_string(),
+                &research.request(var).describe(),
+                &map.node_output(node_id, var, 1).describe(),
+                &var.get_crew(),
+                &research.cooldown(var, 1),
+            ],
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn tooltip(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut hover: ResMut<Hover>,
+    query_tooltip: Query<Entity, With<UiTooltip>>,
+    query_nodes: Query<(&Transform, &Node)>,
+    query_planets: Query<(&Transform, &Planet)>,
+    query_buttons: Query<(&Interaction, &UiButton)>,
+    query_windows: Query<&Window, With<PrimaryWindow>>,
+    query_camera: Query<(&Camera, &GlobalTransform)>,
+    ship_q: Query<&Ship>,
+    map: Res<Map>,
+    research: Res<Research>,
+    nav: Res<NavCursor>,
+    time: Res<Time>,
+) {
+    let (camera, camera_transform) = query_camera.single();
+    let (mouse_viewport, mouse) = match nav.world_position(&map) {
+        Some(pos) if nav.active => {
+            let Some(viewport) = camera.world_to_viewport(camera_transform, pos.extend(0.)) else {
+                return;
+            };
+            (viewport, pos)
+        }
+        _ => {
+            let Some(mouse_viewport) = query_windows.single().cursor_position() else {
+                return;
+            };
+            let mouse = camera
+                .viewport_to_world_2d(camera_transform, mouse_viewport)
+                .unwrap_or(Vec2::ZERO);
+            (mouse_viewport, mouse)
+        }
+    };
+
+    let mut target = None;
+    for (interaction, button) in query_buttons.iter() {
+        if let (Interaction::Hovered, UiButton::Construct(id, var)) = (interaction, button) {
+            target = Some(HoverTarget::Construct(id.clone(), var.clone()));
+        }
+    }
+    if target.is_none() {
+        for (tr, node) in query_nodes.iter() {
+            let rect = Rect::from_center_size(tr.translation.truncate(), TILE_SIZE);
+            if rect.contains(mouse) && map.is_node_scouted(&node.id) {
+                target = Some(HoverTarget::Node(node.id.clone()));
+            }
+        }
+    }
+    if target.is_none() {
+        for (tr, planet) in query_planets.iter() {
+            let rect = Rect::from_center_size(tr.translation.truncate(), TILE_SIZE);
+            if rect.contains(mouse) {
+                target = Some(HoverTarget::Planet(planet.id.clone()));
+            }
+        }
+    }
+
+    if target != hover.target {
+        hover.target = target;
+        hover.timer.reset();
+        for e in query_tooltip.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+
+    hover.timer.tick(time.delta());
+    if !hover.timer.just_finished() {
+        return;
+    }
+    let Some(target) = &hover.target else {
+        return;
+    };
+
+    let text_style = theme.small(&handles);
+    let text = tooltip_text(target, &map, &research, ship_q.get_single().ok());
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(mouse_viewport.x / theme.scale + 16.),
+                    top: Val::Px(mouse_viewport.y / theme.scale + 16.),
+                    border: UiRect::all(Val::Px(2.0)),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            UiTooltip,
+            InGame,
+        ))
+        .with_children(|root| {
+            root.spawn(TextBundle::from_section(text, text_style));
+        });
+}
+
+#[derive(Component)]
+struct UiToast {
+    timer: Timer,
+}
+
+const TOAST_SECS: f32 = 3.;
+
+fn ui_toasts(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut events: EventReader<Toast>,
+    mut query: Query<(Entity, &mut UiToast)>,
+    time: Res<Time>,
+) {
+    let mut shown = 0;
+    for (e, mut toast) in query.iter_mut() {
+        toast.timer.tick(time.delta());
+        if toast.timer.finished() {
+            commands.entity(e).despawn_recursive();
+        } else {
+            shown += 1;
+        }
+    }
+    let text_style = theme.normal(&handles);
+    for toast in events.iter() {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.),
+                        top: Val::Px(10. + 40. * shown as f32),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    z_index: ZIndex::Global(20),
+                    ..default()
+                },
+                UiToast {
+                    timer: Timer::from_seconds(TOAST_SECS, TimerMode::Once),
+                },
+            ))
+            .with_children(|root| {
+                root.spawn(NodeBundle {
+                    style: Style {
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    background_color: theme.panel.into(),
+                    border_color: theme.border.into(),
+                    ..default()
+                })
+                .with_children(|panel| {
+                    panel.spawn(TextBundle::from_section(
+                        toast.text.clone(),
+                        text_style.clone(),
+                    ));
+                });
+            });
+        shown += 1;
+    }
+}
+
+#[derive(Component)]
+enum UiButton {
+    ConstructMenu(NodeId),
+    DestroyMenu(NodeId),
+    MoveMenu(NodeId, bool),
+    Construct(NodeId, ConstructionVariant),
+    EndTurn,
+    Stats,
+    ExportStats,
+    TutorialNext,
+    TutorialSkip,
+    WakeCrew,
+    SleepCrew,
+    Research,
+    PickResearch(TechId),
+    Orbits,
+    Upgrade(NodeId),
+    ShuttleMenu(NodeId),
+    LaunchShuttle(NodeId, GroupId),
+    CancelShuttle(usize),
+    LaunchProbe(GroupId, GroupId),
+    EncounterMenu(usize),
+    Trade(usize, usize),
+    Salvage(usize),
+}
+
+#[derive(Component)]
+struct UiSelectedMoveShip;
+
+fn move_hotkeys(
+    keys: Res<Input<KeyCode>>,
+    q: Query<&UiCanHotkey>,
+    mut event_ui: EventWriter<UiEvent>,
+) {
+    if let Ok(UiCanHotkey(id)) = q.get_single() {
+        if keys.just_pressed(KeyCode::A) {
+            event_ui.send(UiEvent::SelectNodeForMove(id.clone(), false));
+        }
+        if keys.just_pressed(KeyCode::S) {
+            event_ui.send(UiEvent::SelectNodeForMove(id.clone(), true));
+        }
+    }
+}
+
+#[derive(Component)]
+struct UiNodeSelectedMove;
+
+#[derive(Component)]
+struct UiCanHotkey(NodeId);
+
+#[derive(Component)]
+struct MovingTo(NodeId, Vec<NodeId>, bool);
+
+#[allow(clippy::too_many_arguments)]
+fn ui_on_node_selected_move(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut event_ui: EventReader<UiEvent>,
+    query_ui_sel: Query<(Entity, &UiNodeSelectedMove)>,
+    query_selected: Query<(Entity, &SelectedMove)>,
+    query_moving_to: Query<(Entity, &MovingTo)>,
+    map: Res<Map>,
+) {
+    if event_ui.is_empty() {
+        return;
+    }
+
+    for (e, _) in query_moving_to.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for (e, _) in query_ui_sel.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for (e, _) in query_selected.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let event = event_ui
+        .iter()
+        .find(|e| matches!(e, UiEvent::SelectNodeForMove(_, _)));
+    let Some(UiEvent::SelectNodeForMove(id, split)) = event else {
+        return;
+    };
+
+    let big_text_style = theme.big(&handles);
+
+    let mut nears = vec![];
+
+    let Ok(group_id) = map.group_from_node(id) else {
+        return;
+    };
+    let mut star: Vec<GroupId> = map
+        .star(&group_id)
+        .iter()
+        .filter(|neigh_id| {
+            if group_id == GroupId(0) {
+                true
+            } else {
+                neigh_id == &&GroupId(0)
+            }
+        })
+        .cloned()
+        .collect();
+    star.push(group_id.clone());
+    for neighbor_group_id in star.iter() {
+        for node_id in map.groups.get(neighbor_group_id).unwrap().iter() {
+            nears.push(node_id.clone());
+            let pos = map.positions.get(node_id).unwrap();
+            // if planet, red
+            commands.spawn((
+                SpriteSheetBundle {
+                    transform: Transform::default().with_translation(pos.extend(4.)),
+                    sprite: TextureAtlasSprite {
+                        index: 2,
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    ..Default::default()
+                },
+                SelectedMove,
+                InGame,
+            ));
+        }
+    }
+
+    commands.spawn((MovingTo(id.clone(), nears, *split), InGame));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(0.),
+                    right: Val::Percent(0.),
+                    width: Val::Percent(25.),
+                    height: Val::Percent(100.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                ..default()
+            },
+            UiNodeSelectedMove,
+            InGame,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(tr("move.title"), big_text_style.clone()).with_style(
+                    Style {
+                        position_type: PositionType::Relative,
+                        top: Val::Percent(0.),
+                        right: Val::Percent(0.),
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
+            );
+        });
+}
+
+#[derive(Component)]
+struct UiNodeSelectedConstr;
+
+#[allow(clippy::too_many_arguments)]
+fn ui_on_node_selected_constr(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut event_ui: EventReader<UiEvent>,
+    query_ui_sel: Query<(Entity, &UiNodeSelectedConstr)>,
+    query_selected: Query<(Entity, &Selected)>,
+    query_hot: Query<(Entity, &UiCanHotkey)>,
+    map: Res<Map>,
+    grid: Res<PowerGrid>,
+) {
+    if event_ui.is_empty() {
+        return;
+    }
+
+    for (e, _) in query_hot.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for (e, _) in query_ui_sel.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for (e, _) in query_selected.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let event = event_ui
+        .iter()
+        .find(|e| matches!(e, UiEvent::SelectNodeForConstruction(_)));
+    let Some(UiEvent::SelectNodeForConstruction(id)) = event else {
+        return;
+    };
+
+    commands.spawn((UiCanHotkey(id.clone()), InGame));
+
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
+
+    let pos = map.positions.get(id).unwrap();
+    commands.spawn((
+        SpriteSheetBundle {
+            transform: Transform::default().with_translation(pos.extend(4.)),
+            sprite: TextureAtlasSprite {
+                index: 2,
+                ..Default::default()
+            },
+            texture_atlas: handles.atlas.clone(),
+            ..Default::default()
+        },
+        Selected,
+        InGame,
+    ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(0.),
+                    right: Val::Percent(0.),
+                    width: Val::Percent(25.),
+                    height: Val::Percent(100.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                ..default()
+            },
+            UiNodeSelectedConstr,
+            InGame,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(tr("node.title"), big_text_style.clone()).with_style(
+                    Style {
+                        position_type: PositionType::Relative,
+                        top: Val::Percent(0.),
+                        right: Val::Percent(0.),
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
+            );
+            if let Some(power) = map
+                .group_from_node(id)
+                .ok()
+                .and_then(|group_id| grid.last_turn.get(&group_id))
+            {
+                root.spawn(
+                    TextBundle::from_section(
+                        trf(
+                            "node.power",
+                            &[
+                                &power.generated,
+                                &power.demand,
+                                &format!("{:+}", power.generated as i32 - power.demand as i32),
+                                &power.stored,
+                                &power.capacity,
+                            ],
+                        ),
+                        small_text_style.clone(),
+                    )
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    }),
+                );
+            }
+            if !map.is_node_scouted(id) {
+                root.spawn(TextBundle::from_section(
+                    tr("node.unexplored"),
+                    text_style.clone(),
+                ));
+                return;
+            }
+            if let Ok(group_id) = map.group_from_node(id) {
+                root.spawn(
+                    TextBundle::from_section(
+                        trf("node.reserves", &[&map.reserves(&group_id).describe()]),
+                        small_text_style.clone(),
+                    )
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    }),
+                );
+            }
+            let is_ship_present = map.group_from_node(id).is_ok_and(|group_id| {
+                group_id == GroupId(0) || map.star(&GroupId(0)).contains(&group_id)
+            });
+            if !is_ship_present {
+                root.spawn(
+                    TextBundle::from_section(tr("node.too_far"), text_style.clone()).with_style(
+                        Style {
+                            margin: UiRect::bottom(Val::Px(10.)),
+                            ..default()
+                        },
+                    ),
+                );
+                // shuttles don't need the ship around
+                if let Some(NodeOccupant::Stockpile { .. }) = map.occupation.get(id) {
+                    spawn_shuttle_button(root, &theme, &big_text_style, id);
+                }
+                return;
+            }
+            let occ = map.occupation.get(id);
+            if occ.is_none() {
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            border: UiRect::all(Val::Px(3.0)),
+                            margin: UiRect::all(Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: theme.button_border.into(),
+                        ..Default::default()
+                    },
+                    UiButton::ConstructMenu(id.clone()),
+                ))
+                .with_children(|button| {
+                    button.spawn(
+                        TextBundle::from_section(tr("node.construct"), big_text_style.clone())
+                            .with_style(Style {
+                                position_type: PositionType::Relative,
+                                ..default()
+                            }),
+                    );
+                });
+            } else if let Some(NodeOccupant::Construction {
+                var,
+                cooldown,
+                tier,
+            }) = occ
+            {
+                root.spawn(
+                    TextBundle::from_section(
+                        trf("node.building", &[&var.to_string(), tier, &MAX_TIER]),
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Relative,
+                        ..default()
+                    }),
+                );
+                root.spawn(
+                    TextBundle::from_section(
+                        trf("node.will_produce", &[cooldown]),
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Relative,
+                        ..default()
+                    }),
+                );
+                if *tier < MAX_TIER {
+                    let cost = var.upgrade_cost(*tier);
+                    let cash = *map
+                        .group_from_node(id)
+                        .and_then(|group_id| map.get_group_bunch(&group_id))
+                        .unwrap_or_default()
+                        .res
+                        .get(&ResourceVariant::Material)
+                        .unwrap_or(&0);
+                    root.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                border: UiRect::all(Val::Px(3.0)),
+                                margin: UiRect::all(Val::Px(2.)),
+                                ..Default::default()
+                            },
+                            background_color: theme.button.into(),
+                            border_color: theme.button_border.into(),
+                            ..Default::default()
+                        },
+                        UiButton::Upgrade(id.clone()),
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(
+                            trf("node.upgrade", &[&(tier + 1)]),
+                            big_text_style.clone(),
+                        ));
+                        button.spawn(TextBundle::from_section(
+                            trf(
+                                if cash >= cost {
+                                    "construct.cost"
+                                } else {
+                                    "construct.cost_missing"
+                                },
+                                &[
+                                    &cost,
+                                    &ResourceVariant::Material.to_string(),
+                                    &cash,
+                                    &ResourceVariant::Material.to_string(),
+                                ],
+                            ),
+                            if cash >= cost {
+                                small_text_style.clone()
+                            } else {
+                                TextStyle {
+                                    color: theme.negative,
+                                    ..small_text_style.clone()
+                                }
+                            },
+                        ));
+                        button.spawn(TextBundle::from_section(
+                            trf(
+                                "node.upgrade_gives",
+                                &[
+                                    &map.node_output(id, var, tier + 1).describe(),
+                                    &var.cooldown_at(tier + 1),
+                                ],
+                            ),
+                            small_text_style.clone(),
+                        ));
+                    });
+                }
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            border: UiRect::all(Val::Px(3.0)),
+                            margin: UiRect::all(Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: theme.button_border.into(),
+                        ..Default::default()
+                    },
+                    UiButton::DestroyMenu(id.clone()),
+                ))
+                .with_children(|button| {
+                    button.spawn(
+                        TextBundle::from_section(tr("node.demolish"), big_text_style.clone())
+                            .with_style(Style {
+                                position_type: PositionType::Relative,
+                                ..default()
+                            }),
+                    );
+                });
+            } else if let Some(NodeOccupant::Stockpile { var, amt }) = occ {
+                root.spawn(
+                    TextBundle::from_section(
+                        trf("node.stockpile", &[amt, &var.to_string()]),
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Relative,
+                        ..default()
+                    }),
+                );
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            border: UiRect::all(Val::Px(3.0)),
+                            margin: UiRect::all(Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: theme.button_border.into(),
+                        ..Default::default()
+                    },
+                    UiButton::MoveMenu(id.clone(), false),
+                ))
+                .with_children(|button| {
+                    button.spawn(
+                        TextBundle::from_section(tr("node.move_all"), big_text_style.clone())
+                            .with_style(Style {
+                                position_type: PositionType::Relative,
+                                ..default()
+                            }),
+                    );
+                    button.spawn(TextBundle::from_section(
+                        trf("hotkey", &[&"a"]),
+                        text_style.clone(),
+                    ));
+                });
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            border: UiRect::all(Val::Px(3.0)),
+                            margin: UiRect::all(Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: theme.button_border.into(),
+                        ..Default::default()
+                    },
+                    UiButton::MoveMenu(id.clone(), true),
+                ))
+                .with_children(|button| {
+                    button.spawn(
+                        TextBundle::from_section(tr("node.move_half"), big_text_style.clone())
+                            .with_style(Style {
+                                position_type: PositionType::Relative,
+                                ..default()
+                            }),
+                    );
+                    button.spawn(TextBundle::from_section(
+                        trf("hotkey", &[&"s"]),
+                        text_style.clone(),
+                    ));
+                });
+                if map
+                    .group_from_node(id)
+                    .is_ok_and(|group_id| group_id != GroupId(0))
+                {
+                    spawn_shuttle_button(root, &theme, &big_text_style, id);
+                }
+            }
+        });
+}
+
+fn spawn_shuttle_button(root: &mut ChildBuilder, theme: &Theme, style: &TextStyle, id: &NodeId) {
+    root.spawn((
+        ButtonBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                border: UiRect::all(Val::Px(3.0)),
+                margin: UiRect::all(Val::Px(2.)),
+                ..Default::default()
+            },
+            background_color: theme.button.into(),
+            border_color: theme.button_border.into(),
+            ..Default::default()
+        },
+        UiButton::ShuttleMenu(id.clone()),
+    ))
+    .with_children(|button| {
+        button.spawn(TextBundle::from_section(tr("node.shuttle"), style.clone()));
+    });
+}
+
+#[derive(Component)]
+struct UiShuttleMenu;
+
+fn ui_shuttle_menu(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut event_ui: EventReader<UiEvent>,
+    query_ui: Query<Entity, With<UiShuttleMenu>>,
+    map: Res<Map>,
+) {
+    if event_ui.is_empty() {
+        return;
+    }
+
+    for e in query_ui.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let event = event_ui
+        .iter()
+        .find(|e| matches!(e, UiEvent::ShuttleFrom(_)));
+    let Some(UiEvent::ShuttleFrom(id)) = event else {
+        return;
+    };
+    let Ok(from) = map.group_from_node(id) else {
+        return;
+    };
+    let Some(NodeOccupant::Stockpile { var, amt }) = map.occupation.get(id) else {
+        return;
+    };
+
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
+
+    let mut fuel = *map
+        .get_group_bunch(&from)
+        .unwrap_or_default()
+        .res
+        .get(&ResourceVariant::RocketFuel)
+        .unwrap_or(&0);
+    if *var == ResourceVariant::RocketFuel {
+        fuel -= amt;
+    }
+    let mut destinations: Vec<(GroupId, u32, u32)> = map
+        .groups
+        .keys()
+        .filter(|to| map.is_scouted(to))
+        .filter_map(|to| {
+            Shuttles::plan(&map, &from, to).map(|(cost, turns)| (to.clone(), cost, turns))
+        })
+        .collect();
+    destinations.sort_by_key(|(to, _, turns)| (*turns, to.0));
+
+    let pos = map.positions.get(id).unwrap();
+    commands.spawn((
+        SpriteSheetBundle {
+            transform: Transform::default().with_translation(pos.extend(4.)),
+            sprite: TextureAtlasSprite {
+                index: 2,
+                ..Default::default()
+            },
+            texture_atlas: handles.atlas.clone(),
+            ..Default::default()
+        },
+        Selected,
+        InGame,
+    ));
+    for (to, _, _) in destinations.iter() {
+        let pos = map.group_positions.get(to).unwrap();
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default().with_translation(pos.extend(4.)),
+                sprite: TextureAtlasSprite {
+                    index: 5,
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            SelectedMove,
+            InGame,
+        ));
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(0.),
+                    right: Val::Percent(0.),
+                    width: Val::Percent(25.),
+                    height: Val::Percent(100.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                ..default()
+            },
+            UiShuttleMenu,
+            InGame,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(tr("shuttle.title"), big_text_style.clone()).with_style(
+                    Style {
+                        position_type: PositionType::Relative,
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
+            );
+            root.spawn(
+                TextBundle::from_section(
+                    trf("shuttle.cargo", &[amt, &var.to_string(), &fuel]),
+                    text_style.clone(),
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(10.)),
+                    ..default()
+                }),
+            );
+            if destinations.is_empty() {
+                root.spawn(TextBundle::from_section(
+                    trf("shuttle.none", &[&SHUTTLE_RANGE]),
+                    text_style.clone(),
+                ));
+            }
+            for (to, cost, turns) in destinations {
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            border: UiRect::all(Val::Px(3.0)),
+                            margin: UiRect::all(Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: theme.button_border.into(),
+                        ..Default::default()
+                    },
+                    UiButton::LaunchShuttle(id.clone(), to.clone()),
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(
+                        trf("shuttle.planet", &[&to.0]),
+                        text_style.clone(),
+                    ));
+                    button.spawn(TextBundle::from_section(
+                        trf(
+                            "shuttle.cost",
+                            &[&cost, &ResourceVariant::RocketFuel.to_string(), &turns],
+                        ),
+                        if cost <= fuel {
+                            small_text_style.clone()
+                        } else {
+                            TextStyle {
+                                color: theme.negative,
+                                ..small_text_style.clone()
+                            }
+                        },
+                    ));
+                });
+            }
+        });
+}
+
+#[derive(Component)]
+struct ShuttleSprite;
+
+/// in-flight shuttles sit between the two planets, closer to the destination every turn
+fn draw_shuttles(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    map: Res<Map>,
+    shuttles: Res<Shuttles>,
+    query: Query<Entity, With<ShuttleSprite>>,
+) {
+    if !shuttles.is_changed() && !map.is_changed() {
+        return;
+    }
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for flight in shuttles.flights.iter() {
+        let (Some(from), Some(to)) = (
+            map.group_positions.get(&flight.from),
+            map.group_positions.get(&flight.to),
+        ) else {
+            continue;
+        };
+        let dir = *to - *from;
+        commands
+            .spawn((
+                SpriteSheetBundle {
+                    transform: Transform::default()
+                        .with_translation(from.lerp(*to, flight.progress()).extend(3.))
+                        .with_rotation(Quat::from_rotation_z(dir.y.atan2(dir.x) - PI / 2.))
+                        .with_scale(Vec3::splat(0.6)),
+                    sprite: TextureAtlasSprite {
+                        index: 28,
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    ..Default::default()
+                },
+                ShuttleSprite,
+                InGame,
+            ))
+            .with_children(|builder| {
+                builder.spawn(SpriteSheetBundle {
+                    transform: Transform::default()
+                        .with_translation(Vec3::new(0., -48., 0.1))
+                        .with_scale(Vec3::splat(0.6)),
+                    sprite: TextureAtlasSprite {
+                        index: flight.var.get_sprite_index(),
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    ..Default::default()
+                });
+            });
+    }
+}
+
+#[derive(Component)]
+struct EncounterSprite;
+
+/// traders and wrecks wait next to their planet
+fn draw_encounters(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    map: Res<Map>,
+    encounters: Res<Encounters>,
+    query: Query<Entity, With<EncounterSprite>>,
+) {
+    if !encounters.is_changed() && !map.is_changed() {
+        return;
+    }
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for encounter in encounters.active.iter() {
+        let Some(pos) = map.group_positions.get(&encounter.group) else {
+            continue;
+        };
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default()
+                    .with_translation((*pos + Vec2::new(-48., 48.)).extend(3.))
+                    .with_scale(Vec3::splat(0.6)),
+                sprite: TextureAtlasSprite {
+                    index: encounter.sprite_index(),
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
+                ..Default::default()
+            },
+            EncounterSprite,
+            InGame,
+        ));
+    }
+}
+
+/// a trade or salvage button, what the ship gets on top and what it pays below
+fn spawn_deal(
+    root: &mut ChildBuilder,
+    theme: &Theme,
+    handles: &AssetHandles,
+    button: UiButton,
+    pay: (&ResourceVariant, u32),
+    get: (&ResourceVariant, u32),
+    afford: bool,
+) {
+    let small_text_style = theme.small(handles);
+    root.spawn((
+        ButtonBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                border: UiRect::all(Val::Px(3.0)),
+                margin: UiRect::all(Val::Px(2.)),
+                ..Default::default()
+            },
+            background_color: theme.button.into(),
+            border_color: theme.button_border.into(),
+            ..Default::default()
+        },
+        button,
+    ))
+    .with_children(|button| {
+        button.spawn(TextBundle::from_section(
+            trf("encounter.get", &[&get.1, &get.0.to_string()]),
+            theme.normal(handles),
+        ));
+        button.spawn(TextBundle::from_section(
+            trf("encounter.pay", &[&pay.1, &pay.0.to_string()]),
+            if afford {
+                small_text_style.clone()
+            } else {
+                TextStyle {
+                    color: theme.negative,
+                    ..small_text_style
+                }
+            },
+        ));
+    });
+}
+
+#[derive(Component)]
+struct UiEncounter;
+
+#[allow(clippy::too_many_arguments)]
+fn ui_encounter(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut event_ui: EventReader<UiEvent>,
+    query_ui: Query<Entity, With<UiEncounter>>,
+    map: Res<Map>,
+    encounters: Res<Encounters>,
+    ship_q: Query<&Ship>,
+) {
+    if event_ui.is_empty() {
+        return;
+    }
+
+    for e in query_ui.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let event = event_ui.iter().find(|e| matches!(e, UiEvent::Encounter(_)));
+    let Some(UiEvent::Encounter(id)) = event else {
+        return;
+    };
+    let Some(encounter) = encounters.active.iter().find(|e| e.id == *id) else {
+        return;
+    };
+    let Ok(ship) = ship_q.get_single() else {
+        return;
+    };
+    let here = ship.orbiting_group == encounter.group;
+    let hold = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+    let afford = |var: &ResourceVariant, amt: u32| here && *hold.res.get(var).unwrap_or(&0) >= amt;
+
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(0.),
+                    right: Val::Percent(0.),
+                    width: Val::Percent(25.),
+                    height: Val::Percent(100.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                ..default()
+            },
+            UiEncounter,
+            InGame,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(encounter.name(), big_text_style.clone()).with_style(
+                    Style {
+                        position_type: PositionType::Relative,
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
+            );
+            root.spawn(
+                TextBundle::from_section(
+                    trf(
+                        "encounter.where",
+                        &[&encounter.group.0, &encounter.turns_left],
+                    ),
+                    text_style.clone(),
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(10.)),
+                    ..default()
+                }),
+            );
+            if !here {
+                root.spawn(TextBundle::from_section(
+                    trf("encounter.away", &[&encounter.group.0]),
+                    TextStyle {
+                        color: theme.negative,
+                        ..text_style.clone()
+                    },
+                ));
+            }
+            match &encounter.kind {
+                EncounterKind::Trader(offers) => {
+                    for offer in offers.iter() {
+                        let (give, give_amt, get, get_amt) = &TRADES[*offer];
+                        spawn_deal(
+                            root,
+                            &theme,
+                            &handles,
+                            UiButton::Trade(encounter.id, *offer),
+                            (give, *give_amt),
+                            (get, *get_amt),
+                            afford(give, *give_amt),
+                        );
+                    }
+                }
+                EncounterKind::Wreck(material) => {
+                    spawn_deal(
+                        root,
+                        &theme,
+                        &handles,
+                        UiButton::Salvage(encounter.id),
+                        (&ResourceVariant::RocketFuel, SALVAGE_FUEL),
+                        (&ResourceVariant::Material, *material),
+                        afford(&ResourceVariant::RocketFuel, SALVAGE_FUEL),
+                    );
+                }
+            }
+        });
+}
+
+/// pay a probe with the ship's rocket fuel, it reveals the planet right away
+fn launch_probe(map: &mut Map, from: &GroupId, to: &GroupId) -> Result<Vec<AutoAction>, MapError> {
+    if map.is_scouted(to) {
+        return Err(MapError::Scouted(to.clone()));
+    }
+    let jumps = map
+        .distance(from, to)
+        .ok_or(MapError::OutOfRange(to.clone()))?;
+    let paid = map.take_resource_in_group(
+        &GroupId(0),
+        &ResourceVariant::RocketFuel,
+        jumps.max(1) * PROBE_FUEL_PER_JUMP,
+    )?;
+    map.scout(to);
+    Ok(paid
+        .into_iter()
+        .map(|(from, abs, diff)| AutoAction::ConsumeResource {
+            from: from.clone(),
+            to: from,
+            var: ResourceVariant::RocketFuel,
+            abs,
+            diff: -diff,
+            taken: diff as u32,
+        })
+        .collect())
+}
+
+#[derive(Component)]
+struct UiScoutPlanet;
+
+fn ui_scout_planet(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut event_ui: EventReader<UiEvent>,
+    query_ui: Query<Entity, With<UiScoutPlanet>>,
+    map: Res<Map>,
+) {
+    if event_ui.is_empty() {
+        return;
+    }
+
+    for e in query_ui.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let event = event_ui
+        .iter()
+        .find(|e| matches!(e, UiEvent::ScoutPlanet(_, _)));
+    let Some(UiEvent::ScoutPlanet(from, to)) = event else {
+        return;
+    };
+
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
+    let fuel = *map
+        .get_group_bunch(&GroupId(0))
+        .unwrap_or_default()
+        .res
+        .get(&ResourceVariant::RocketFuel)
+        .unwrap_or(&0);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(0.),
+                    right: Val::Percent(0.),
+                    width: Val::Percent(25.),
+                    height: Val::Percent(100.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                ..default()
+            },
+            UiScoutPlanet,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(trf("scout.title", &[&to.0]), big_text_style.clone())
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    }),
+            );
+            root.spawn(
+                TextBundle::from_section(tr("scout.help"), text_style.clone()).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(10.)),
+                    ..default()
+                }),
+            );
+            let Some(jumps) = map.distance(from, to) else {
+                root.spawn(TextBundle::from_section(
+                    tr("tooltip.unreachable"),
+                    text_style.clone(),
+                ));
+                return;
+            };
+            let cost = jumps.max(1) * PROBE_FUEL_PER_JUMP;
+            root.spawn((
+                ButtonBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        border: UiRect::all(Val::Px(3.0)),
+                        margin: UiRect::all(Val::Px(2.)),
+                        ..Default::default()
+                    },
+                    background_color: theme.button.into(),
+                    border_color: theme.button_border.into(),
+                    ..Default::default()
+                },
+                UiButton::LaunchProbe(from.clone(), to.clone()),
+            ))
+            .with_children(|button| {
+                button.spawn(TextBundle::from_section(
+                    tr("scout.launch"),
+                    big_text_style.clone(),
+                ));
+                button.spawn(TextBundle::from_section(
+                    trf(
+                        "scout.cost",
+                        &[&cost, &ResourceVariant::RocketFuel.to_string(), &fuel],
+                    ),
+                    if cost <= fuel {
+                        small_text_style.clone()
+                    } else {
+                        TextStyle {
+                            color: theme.negative,
+                            ..small_text_style.clone()
+                        }
+                    },
+                ));
+            });
+        });
+}
+
+#[derive(Component)]
+struct UnexploredMarker;
+
+/// slots of unexplored groups are hidden, their planet gets a question mark
+fn fog_of_war(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    map: Res<Map>,
+    mut query_nodes: Query<(&Node, &mut Visibility)>,
+    query_markers: Query<Entity, With<UnexploredMarker>>,
+) {
+    if !map.is_changed() {
+        return;
+    }
+    for (node, mut visibility) in query_nodes.iter_mut() {
+        visibility.set_if_neq(if map.is_node_scouted(&node.id) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    for e in query_markers.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    // over the dark planet, not a sprite
+    let text_style = TextStyle {
+        color: theme.text,
+        ..theme.world(theme.huge(&handles))
+    };
+    for (group_id, pos) in map.group_positions.iter() {
+        if map.is_scouted(group_id) {
+            continue;
+        }
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("?", text_style.clone())
+                    .with_alignment(TextAlignment::Center),
+                transform: Transform::default().with_translation(pos.extend(2.)),
+                ..default()
+            },
+            UnexploredMarker,
+            InGame,
+        ));
+    }
+}
+
+/// pay the material cost of a construction and place it
+fn buy_construction(
+    map: &mut Map,
+    node_id: &NodeId,
+    var: &ConstructionVariant,
+) -> Result<Vec<AutoAction>, MapError> {
+    let group_id = map.group_from_node(node_id)?;
+    if !map.is_scouted(&group_id) {
+        return Err(MapError::Unexplored(group_id));
+    }
+    if map.occupation.get(node_id).is_some() {
+        return Err(MapError::NoSpace(node_id.clone()));
+    }
+    let paid = map.take_resource_in_group(
+        &group_id,
+        &ResourceVariant::Material,
+        var.get_material_cost(),
+    )?;
+    map.construct(node_id, var)?;
+    Ok(paid
+        .into_iter()
+        .map(|(from, abs, diff)| AutoAction::ConsumeResource {
+            from,
+            to: node_id.clone(),
+            var: ResourceVariant::Material,
+            abs,
+            diff: var.get_material_cost() as i32,
+            taken: diff as u32,
+        })
+        .collect())
+}
+
+/// pay the material cost of the next tier and upgrade the construction
+fn buy_upgrade(map: &mut Map, node_id: &NodeId) -> Result<(u32, Vec<AutoAction>), MapError> {
+    let group_id = map.group_from_node(node_id)?;
+    let Some(NodeOccupant::Construction { var, tier, .. }) = map.occupation.get(node_id).cloned()
+    else {
+        return Err(MapError::NoConstruction(node_id.clone()));
+    };
+    if tier >= MAX_TIER {
+        return Err(MapError::MaxTier(node_id.clone()));
+    }
+    let cost = var.upgrade_cost(tier);
+    let paid = map.take_resource_in_group(&group_id, &ResourceVariant::Material, cost)?;
+    let tier = map.upgrade(node_id)?;
+    Ok((
+        tier,
+        paid.into_iter()
+            .map(|(from, abs, diff)| AutoAction::ConsumeResource {
+                from,
+                to: node_id.clone(),
+                var: ResourceVariant::Material,
+                abs,
+                diff: cost as i32,
+                taken: diff as u32,
+            })
+            .collect(),
+    ))
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn button_system(
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &mut BorderColor,
+            &UiButton,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    theme: Res<Theme>,
+    mut event_ui: EventWriter<UiEvent>,
+    mut event_construct: EventWriter<BuildConstruction>,
+    mut event_destruct: EventWriter<DestroyConstruction>,
+    mut event_upgrade: EventWriter<UpgradeConstruction>,
+    mut events_end: EventWriter<EndTurn>,
+    mut map: ResMut<Map>,
+    mut autoactions: ResMut<AutoActions>,
+    mut stats: ResMut<Stats>,
+    mut tutorial: ResMut<Tutorial>,
+    mut toasts: EventWriter<Toast>,
+    mut crew: ResMut<Crew>,
+    mut research: ResMut<Research>,
+    traffic: (ResMut<Shuttles>, ResMut<Encounters>, Query<&Ship>),
+) {
+    let (mut shuttles, mut encounters, ship_q) = traffic;
+    let at = ship_q
+        .get_single()
+        .map(|ship| ship.orbiting_group.clone())
+        .unwrap_or(GroupId(0));
+    for (interaction, mut color, mut border, ui_button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = theme.button_pressed.into();
+                *border = theme.text.into();
+                match ui_button {
+                    UiButton::ConstructMenu(id) => {
+                        event_ui.send(UiEvent::ConstructOnNode(id.clone()));
+                    }
+                    UiButton::Construct(node_id, var) => {
+                        match buy_construction(&mut map, node_id, var) {
+                            Ok(consumed) => {
+                                event_construct.send(BuildConstruction {
+                                    node_id: node_id.clone(),
+                                    var: var.clone(),
+                                });
+                                *stats.built.entry(var.clone()).or_default() += 1;
+                                event_ui.send(UiEvent::SelectNodeForConstruction(node_id.clone()));
+                                autoactions.actions.extend(consumed);
+                                autoactions.start();
+                            }
+                            Err(err) => toasts.send(Toast {
+                                text: err.to_string(),
+                            }),
+                        }
+                    }
+                    UiButton::Upgrade(node_id) => match buy_upgrade(&mut map, node_id) {
+                        Ok((tier, consumed)) => {
+                            event_upgrade.send(UpgradeConstruction {
+                                node_id: node_id.clone(),
+                                tier,
+                            });
+                            event_ui.send(UiEvent::SelectNodeForConstruction(node_id.clone()));
+                            autoactions.actions.extend(consumed);
+                            autoactions.start();
+                        }
+                        Err(err) => toasts.send(Toast {
+                            text: err.to_string(),
+                        }),
+                    },
+                    UiButton::DestroyMenu(node_id) => {
+                        map.occupation.remove(node_id);
+                        event_destruct.send(DestroyConstruction {
+                            node_id: node_id.clone(),
+                        });
+                        event_ui.send(UiEvent::Close);
+                    }
+                    UiButton::MoveMenu(node_id, split) => {
+                        event_ui.send(UiEvent::SelectNodeForMove(node_id.clone(), *split));
+                    }
+                    UiButton::ShuttleMenu(node_id) => {
+                        event_ui.send(UiEvent::ShuttleFrom(node_id.clone()));
+                    }
+                    UiButton::LaunchShuttle(node_id, to) => {
+                        match shuttles.launch(&mut map, node_id, to) {
+                            Ok(consumed) => {
+                                event_ui.send(UiEvent::Close);
+                                autoactions.actions.extend(consumed);
+                                autoactions.start();
+                            }
+                            Err(err) => toasts.send(Toast {
+                                text: err.to_string(),
+                            }),
+                        }
+                    }
+                    UiButton::CancelShuttle(id) => {
+                        shuttles.cancel(*id);
+                    }
+                    UiButton::LaunchProbe(from, to) => match launch_probe(&mut map, from, to) {
+                        Ok(consumed) => {
+                            event_ui.send(UiEvent::Close);
+                            toasts.send(Toast {
+                                text: map.scout_report(to),
+                            });
+                            autoactions.actions.extend(consumed);
+                            autoactions.start();
+                        }
+                        Err(err) => toasts.send(Toast {
+                            text: err.to_string(),
+                        }),
+                    },
+                    UiButton::EncounterMenu(id) => {
+                        event_ui.send(UiEvent::Encounter(*id));
+                    }
+                    UiButton::Trade(id, offer) => {
+                        match encounters.trade(&mut map, &at, *id, *offer) {
+                            Ok(consumed) => {
+                                event_ui.send(UiEvent::Encounter(*id));
+                                autoactions.actions.extend(consumed);
+                                autoactions.start();
+                            }
+                            Err(err) => toasts.send(Toast {
+                                text: err.to_string(),
+                            }),
+                        }
+                    }
+                    UiButton::Salvage(id) => match encounters.salvage(&mut map, &at, *id) {
+                        Ok(consumed) => {
+                            event_ui.send(UiEvent::Close);
+                            autoactions.actions.extend(consumed);
+                            autoactions.start();
+                        }
+                        Err(err) => toasts.send(Toast {
+                            text: err.to_string(),
+                        }),
+                    },
+                    UiButton::EndTurn => {
+                        events_end.send(EndTurn);
+                    }
+                    UiButton::Stats => {
+                        event_ui.send(UiEvent::ToggleStats);
+                    }
+                    UiButton::ExportStats => {
+                        export_stats(&stats);
+                    }
+                    UiButton::Research => {
+                        event_ui.send(UiEvent::ToggleResearch);
+                    }
+                    UiButton::PickResearch(id) => {
+                        research.pick(*id);
+                    }
+                    UiButton::Orbits => {
+                        event_ui.send(UiEvent::ToggleOrbits);
+                    }
+                    UiButton::TutorialNext => {
+                        tutorial.step += 1;
+                    }
+                    UiButton::TutorialSkip => {
+                        tutorial.active = false;
+                    }
+                    UiButton::WakeCrew => {
+                        crew.wake();
+                    }
+                    UiButton::SleepCrew => {
+                        crew.sleep();
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = theme.button_hovered.into();
+                *border = theme.text.into();
+            }
+            Interaction::None => {
+                *color = theme.button.into();
+                *border = theme.button_border.into();
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct UiConstruct;
+
+fn ui_on_construction(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    query_ui_cons: Query<(Entity, &UiConstruct)>,
+    mut event_ui: EventReader<UiEvent>,
+    map: Res<Map>,
+    research: Res<Research>,
+) {
+    if event_ui.is_empty() {
+        return;
+    }
+
+    for (e, _) in query_ui_cons.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let event = event_ui
+        .iter()
+        .find(|e| matches!(e, UiEvent::ConstructOnNode(_)));
+    let Some(UiEvent::ConstructOnNode(id)) = event else {
+        return;
+    };
+
+    let pos = map.positions.get(id).unwrap();
+    commands.spawn((
+        SpriteSheetBundle {
+            transform: Transform::default().with_translation(pos.extend(4.)),
+            sprite: TextureAtlasSprite {
+                index: 2,
+                ..Default::default()
+            },
+            texture_atlas: handles.atlas.clone(),
+            ..Default::default()
+        },
+        Selected,
+        InGame,
+    ));
+
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(0.),
+                    right: Val::Percent(0.),
+                    width: Val::Percent(25.),
+                    height: Val::Percent(100.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                ..default()
+            },
+            UiConstruct,
+            InGame,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(tr("construct.title"), big_text_style.clone()).with_style(
+                    Style {
+                        position_type: PositionType::Relative,
+                        top: Val::Percent(0.),
+                        right: Val::Percent(0.),
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
+            );
+            if let Ok(group_id) = map.group_from_node(id) {
+                root.spawn(TextBundle::from_section(
+                    trf(
+                        "construct.sunlight",
+                        &[&((map.irradiance(&group_id) * 100.).round() as u32)],
+                    ),
+                    small_text_style.clone(),
+                ));
+            }
+            for constr in ConstructionVariant::iter().filter(|c| research.unlocked(c)) {
+                let cash = *map
+                    .group_from_node(id)
+                    .and_then(|group_id| map.get_group_bunch(&group_id))
+                    .unwrap_or_default()
+                    .res
+                    .get(&ResourceVariant::Material)
+                    .unwrap_or(&0);
+                let can_buy = cash >= constr.get_material_cost();
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            border: UiRect::all(Val::Px(3.0)),
+                            margin: UiRect::all(Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: theme.button_border.into(),
+                        ..Default::default()
+                    },
+                    UiButton::Construct(id.clone(), constr.clone()),
+                ))
+                .with_children(|constr_node| {
+                    constr_node.spawn(AtlasImageBundle {
+                        style: Style {
+                            width: Val::Px(64.),
+                            height: Val::Px(64.),
+                            ..Default::default()
+                        },
+                        texture_atlas: handles.atlas.clone(),
+                        texture_atlas_image: UiTextureAtlasImage {
+                            index: constr.get_sprite_index(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    });
+                    constr_node
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                margin: UiRect::all(Val::Px(2.)),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .with_children(|details| {
+                            details.spawn(
+                                TextBundle::from_section(constr.to_string(), text_style.clone())
+                                    .with_style(Style {
+                                        position_type: PositionType::Relative,
+                                        ..default()
+                                    }),
+                            );
+                            details.spawn(
+                                TextBundle::from_section(
+                                    trf(
+                                        if can_buy {
+                                            "construct.cost"
+                                        } else {
+                                            "construct.cost_missing"
+                                        },
+                                        &[
+                                            &constr.get_material_cost(),
+                                            &ResourceVariant::Material.to_string(),
+                                            &cash,
+                                            &ResourceVariant::Material.to_string(),
+                                        ],
+                                    ),
+                                    if can_buy {
+                                        small_text_style.clone()
+                                    } else {
+                                        TextStyle {
+                                            color: theme.negative,
+                                            ..small_text_style.clone()
+                                        }
+                                    },
+                                )
+                                .with_style(Style {
+                                    position_type: PositionType::Relative,
+                                    ..default()
+                                }),
+                            );
+                            details.spawn(
+                                TextBundle::from_section(
+                                    trf(
+                                        "construct.generates",
+                                        &[
+                                            &map.node_output(id, &constr, 1).describe(),
+                                            &research.request(&constr).describe(),
+                                            &constr.get_crew(),
+                                            &research.cooldown(&constr, 1),
+                                        ],
+                                    ),
+                                    small_text_style.clone(),
+                                )
+                                .with_style(Style {
+                                    position_type: PositionType::Relative,
+                                    ..default()
+                                }),
+                            );
+                        });
+                });
+            }
+        });
+}
+
+#[derive(Component)]
+struct UiTurnCount;
+
+#[derive(Component)]
+struct UiObjectives;
+
+/// goals first, then what loses the game, coloured by how they stand
+#[allow(clippy::too_many_arguments)]
+fn ui_objectives(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    map: Res<Map>,
+    turns: Res<TurnCount>,
+    crew: Res<Crew>,
+    scenario: Res<Scenario>,
+    query: Query<Entity, With<UiObjectives>>,
+) {
+    let Ok(list) = query.get_single() else {
+        return;
+    };
+    if !map.is_changed() && !turns.is_changed() && !scenario.is_changed() && !crew.is_changed() {
+        return;
+    }
+    let small_text_style = theme.small(&handles);
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|list| {
+        for (goals, title) in [(true, "objectives.win"), (false, "objectives.lose")] {
+            list.spawn(TextBundle::from_section(
+                tr(title),
+                small_text_style.clone(),
+            ));
+            for objective in scenario.objectives.iter().filter(|o| o.is_goal() == goals) {
+                let color = match scenario.status(objective, turns.count, &map, &crew) {
+                    Status::Done if goals => theme.positive,
+                    Status::Failed => theme.negative,
+                    _ => theme.text,
+                };
+                list.spawn(TextBundle::from_section(
+                    objective.describe(&map),
+                    TextStyle {
+                        color,
+                        ..small_text_style.clone()
+                    },
+                ));
+            }
+        }
+    });
+}
+
+fn ui_topleft(turns: Res<TurnCount>, mut query: Query<(&UiTurnCount, &mut Text)>) {
+    if let Ok((_, mut text)) = query.get_single_mut() {
+        text.sections[0].value = trf("topleft.turn", &[&turns.count]);
+    }
+}
+
+#[derive(Component)]
+struct UiHudAmount(ResourceVariant);
+
+#[derive(Component)]
+struct UiHudBar(ResourceVariant);
+
+#[derive(Component)]
+struct UiHudFoodLeft;
+
+#[derive(Component)]
+struct UiHudCrew;
+
+#[derive(Component)]
+struct UiHudShuttles;
+
+#[derive(Component)]
+struct UiHudEncounters;
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn ui_hud(
+    map: Res<Map>,
+    ship_net: Res<ShipNet>,
+    crew: Res<Crew>,
+    scenario: Res<Scenario>,
+    mut query_amt: Query<(&UiHudAmount, &mut Text), (Without<UiHudFoodLeft>, Without<UiHudCrew>)>,
+    mut query_bar: Query<(&UiHudBar, &mut Style)>,
+    mut query_food: Query<&mut Text, (With<UiHudFoodLeft>, Without<UiHudCrew>)>,
+    mut query_crew: Query<&mut Text, (With<UiHudCrew>, Without<UiHudAmount>)>,
+) {
+    let ship = map.get_group_bunch(&GroupId(0)).unwrap_or_default();
+    let amount = |var: &ResourceVariant| *ship.res.get(var).unwrap_or(&0);
+    for (UiHudAmount(var), mut text) in query_amt.iter_mut() {
+        let net = *ship_net.net.get(var).unwrap_or(&0);
+        text.sections[0].value = trf(
+            "hud.amount",
+            &[&amount(var), &var.to_string(), &format!("{:+}", net)],
+        );
+    }
+    for (UiHudBar(var), mut style) in query_bar.iter_mut() {
+        let target = scenario.target(var).unwrap_or(MAX_STOCKPILE);
+        let pct = (amount(var) as f32 / target as f32).min(1.) * 100.;
+        style.width = Val::Percent(pct);
+    }
+    if let Ok(mut text) = query_food.get_single_mut() {
+        text.sections[0].value = trf(
+            "hud.food_left",
+            &[&(amount(&ResourceVariant::Food) / crew.food_needed().max(1))],
+        );
+    }
+    if let Ok(mut text) = query_crew.get_single_mut() {
+        text.sections[0].value = trf("hud.crew", &[&crew.awake, &crew.asleep]);
+    }
+}
+
+fn setup_ui_topleft(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    turns: Res<TurnCount>,
+    scenario: Res<Scenario>,
+) {
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(0.),
+                    left: Val::Percent(0.),
+                    width: Val::Percent(15.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                ..default()
+            },
+            InGame,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                ButtonBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::all(Val::Px(2.)),
+                        ..Default::default()
+                    },
+                    background_color: theme.button.into(),
+                    ..Default::default()
+                },
+                UiButton::EndTurn,
+            ))
+            .with_children(|details| {
+                details.spawn(
+                    TextBundle::from_section(tr("topleft.end_turn"), big_text_style.clone())
+                        .with_style(Style {
+                            position_type: PositionType::Relative,
+                            ..default()
+                        }),
+                );
+                details.spawn((
+                    TextBundle::from_section(
+                        trf("topleft.turn", &[&turns.count]),
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Relative,
+                        ..default()
+                    }),
+                    UiTurnCount,
+                ));
+                details.spawn(TextBundle::from_section(
+                    trf("hotkey", &[&"Delete"]),
+                    text_style.clone(),
+                ));
+                details.spawn(
+                    TextBundle::from_section(
+                        trf("topleft.eat", &[&FOOD_PER_CREW]),
+                        small_text_style.clone(),
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Relative,
+                        margin: UiRect::all(Val::Px(10.)),
+                        ..default()
+                    }),
+                );
+                details.spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            margin: UiRect::all(Val::Px(10.)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    UiObjectives,
+                ));
+                details.spawn(
+                    TextBundle::from_section(tr("topleft.nav"), small_text_style.clone())
+                        .with_style(Style {
+                            position_type: PositionType::Relative,
+                            margin: UiRect::all(Val::Px(10.)),
+                            ..default()
+                        }),
+                );
+            });
+            root.spawn((
+                ButtonBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::all(Val::Px(2.)),
+                        ..Default::default()
+                    },
+                    background_color: theme.button.into(),
+                    ..Default::default()
+                },
+                UiButton::Stats,
+            ))
+            .with_children(|details| {
+                details.spawn(TextBundle::from_section(
+                    tr("topleft.statistics"),
+                    text_style.clone(),
+                ));
+                details.spawn(TextBundle::from_section(
+                    trf("hotkey", &[&"t"]),
+                    small_text_style.clone(),
+                ));
+            });
+            root.spawn((
+                ButtonBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::all(Val::Px(2.)),
+                        ..Default::default()
+                    },
+                    background_color: theme.button.into(),
+                    ..Default::default()
+                },
+                UiButton::Research,
+            ))
+            .with_children(|details| {
+                details.spawn(TextBundle::from_section(
+                    tr("topleft.research"),
+                    text_style.clone(),
+                ));
+                details.spawn(TextBundle::from_section(
+                    trf("hotkey", &[&"r"]),
+                    small_text_style.clone(),
+                ));
+            });
+            root.spawn((
+                ButtonBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::all(Val::Px(2.)),
+                        ..Default::default()
+                    },
+                    background_color: theme.button.into(),
+                    ..Default::default()
+                },
+                UiButton::Orbits,
+            ))
+            .with_children(|details| {
+                details.spawn(TextBundle::from_section(
+                    tr("topleft.orbits"),
+                    text_style.clone(),
+                ));
+                details.spawn(TextBundle::from_section(
+                    trf("hotkey", &[&"o"]),
+                    small_text_style.clone(),
+                ));
+            });
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    margin: UiRect::all(Val::Px(2.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|hud| {
+                hud.spawn(TextBundle::from_section(tr("hud.ship"), text_style.clone()));
+                for var in ResourceVariant::iter().filter(|var| var.is_stockpiled()) {
+                    hud.spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(AtlasImageBundle {
+                            style: Style {
+                                width: Val::Px(24.),
+                                height: Val::Px(24.),
+                                ..Default::default()
+                            },
+                            texture_atlas: handles.atlas.clone(),
+                            texture_atlas_image: UiTextureAtlasImage {
+                                index: var.get_sprite_index(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        });
+                        row.spawn((
+                            TextBundle::from_section("", small_text_style.clone()),
+                            UiHudAmount(var.clone()),
+                        ));
+                    });
+                    if scenario.target(&var).is_some() {
+                        hud.spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.),
+                                height: Val::Px(6.),
+                                margin: UiRect::bottom(Val::Px(4.)),
+                                ..Default::default()
+                            },
+                            background_color: theme.muted.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|bar| {
+                            bar.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        width: Val::Percent(0.),
+                                        height: Val::Percent(100.),
+                                        ..Default::default()
+                                    },
+                                    background_color: theme.text.into(),
+                                    ..Default::default()
+                                },
+                                UiHudBar(var.clone()),
+                            ));
+                        });
+                    }
+                }
+                hud.spawn((
+                    TextBundle::from_section("", small_text_style.clone()),
+                    UiHudFoodLeft,
+                ));
+                hud.spawn((
+                    TextBundle::from_section("", small_text_style.clone()),
+                    UiHudCrew,
+                ));
+                hud.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|row| {
+                    for (button, label) in [
+                        (UiButton::WakeCrew, "hud.wake"),
+                        (UiButton::SleepCrew, "hud.sleep"),
+                    ] {
+                        row.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    margin: UiRect::all(Val::Px(2.)),
+                                    padding: UiRect::all(Val::Px(4.)),
+                                    border: UiRect::all(Val::Px(2.)),
+                                    ..Default::default()
+                                },
+                                background_color: theme.button.into(),
+                                border_color: theme.button_border.into(),
+                                ..Default::default()
+                            },
+                            button,
+                        ))
+                        .with_children(|button| {
+                            button.spawn(TextBundle::from_section(
+                                tr(label),
+                                small_text_style.clone(),
+                            ));
+                        });
+                    }
+                });
+                hud.spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    UiHudShuttles,
+                ));
+                hud.spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            ..Default::default()
                         },
-                    ));
-                }
-            }
-        };
-        autoactions.current = Some(act);
-    }
+                        ..Default::default()
+                    },
+                    UiHudEncounters,
+                ));
+            });
+        });
 }
 
-#[derive(Clone, Debug, Component)]
-struct Highlight;
-#[derive(Clone, Debug, Component)]
-struct Selected;
-#[derive(Clone, Debug, Component)]
-struct SelectedMove;
-
-fn highlight(
+fn ui_hud_shuttles(
     mut commands: Commands,
     handles: Res<AssetHandles>,
-    query_highlight: Query<(Entity, &Highlight)>,
-    query_nodes: Query<(Entity, &Node)>,
-    query_planets: Query<(Entity, &Planet)>,
-    query_tr: Query<&Transform>,
-    query_windows: Query<&Window, With<PrimaryWindow>>,
-    query_camera: Query<(&Camera, &GlobalTransform)>,
-    mut event_ui: EventWriter<UiEvent>,
-    mouse_button_input: Res<Input<MouseButton>>,
-    query_moving_to: Query<&MovingTo>,
-    query_ship_moving_to: Query<&ShipMovingTo>,
-    query_move_ship: Query<(Entity, &UiSelectedMoveShip)>,
-    mut map: ResMut<Map>,
-    mut ship_q: Query<&mut Ship>,
-    mut autoactions: ResMut<AutoActions>,
+    theme: Res<Theme>,
+    shuttles: Res<Shuttles>,
+    query: Query<Entity, With<UiHudShuttles>>,
 ) {
-    for (e, _) in query_move_ship.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for (e, _) in query_highlight.iter() {
-        commands.entity(e).despawn();
-    }
-
-    let Ok(ship) = ship_q.get_single() else {
+    if !shuttles.is_changed() {
         return;
-    };
-
-    let star = map.star(&ship.orbiting_group);
-    let mut nears = vec![];
-    nears.push(ship.orbiting_group.clone());
-    for neighbor_group_id in star.iter() {
-        if neighbor_group_id == &GroupId(0) {
-            // that's a ship not a planet
-            continue;
-        }
-        nears.push(neighbor_group_id.clone());
-        let pos = map.group_positions.get(neighbor_group_id).unwrap();
-        commands.spawn((
-            SpriteSheetBundle {
-                transform: Transform::default().with_translation(pos.extend(0.)),
-                sprite: TextureAtlasSprite {
-                    index: 5,
-                    ..Default::default()
-                },
-                texture_atlas: handles.atlas.clone(),
-                ..Default::default()
-            },
-            UiSelectedMoveShip,
-        ));
     }
-
-    let clicked = mouse_button_input.just_pressed(MouseButton::Left);
-
-    let Some(mouse_viewport) = query_windows.single().cursor_position() else {
+    let Ok(list) = query.get_single() else {
         return;
     };
-
-    let (camera, camera_transform) = query_camera.single();
-    let mouse = camera
-        .viewport_to_world_2d(camera_transform, mouse_viewport)
-        .unwrap_or(Vec2::ZERO);
-
-    let mut found_node = None;
-    for (ent, node) in query_nodes.iter() {
-        let tr = query_tr.get(ent).unwrap();
-        let rect = Rect::from_center_size(
-            Vec2::new(tr.translation.x, tr.translation.y),
-            Vec2::new(64., 64.),
-        );
-        if rect.contains(mouse) {
-            found_node = Some((tr, node));
+    let small_text_style = theme.small(&handles);
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|list| {
+        if !shuttles.flights.is_empty() {
+            list.spawn(TextBundle::from_section(
+                tr("hud.shuttles"),
+                theme.normal(&handles),
+            ));
         }
-    }
-
-    if let Some((tr, node)) = found_node {
-        commands.spawn((
-            SpriteSheetBundle {
-                transform: Transform::default()
-                    .with_translation(tr.translation + Vec3::new(0., 0., 3.)),
-                sprite: TextureAtlasSprite {
-                    color: Color::WHITE.with_a(0.3),
-                    index: 2,
+        for flight in shuttles.flights.iter() {
+            list.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
                     ..Default::default()
                 },
-                texture_atlas: handles.atlas.clone(),
                 ..Default::default()
-            },
-            Highlight,
-        ));
-        if clicked {
-            if let Ok(MovingTo(from_id, nears, split)) = query_moving_to.get_single() {
-                if nears.contains(&node.id) && node.id != *from_id {
-                    let Some(NodeOccupant::Stockpile {
-                        var: from_var,
-                        amt: from_amt_full,
-                    }) = map.occupation.get(from_id).map(|o| o.clone())
-                    else {
-                        return;
-                    };
-
-                    let from_amt = if *split {
-                        from_amt_full / 2
-                    } else {
-                        from_amt_full
-                    };
-
-                    if let Some(NodeOccupant::Stockpile {
-                        var: to_var,
-                        amt: to_amt,
-                    }) = map.occupation.get(&node.id)
-                    {
-                        if *to_var == from_var {
-                            let clamped = (from_amt + to_amt).min(100);
-                            if from_amt == from_amt_full {
-                                map.occupation.remove(from_id);
-                                autoactions.actions.push(AutoAction::ConsumeResource {
-                                    from: from_id.clone(),
-                                    to: node.id.clone(),
-                                    var: from_var.clone(),
-                                    abs: 0,
-                                    diff: from_amt as i32,
-                                });
-                            } else {
-                                map.occupation.insert(
-                                    from_id.clone(),
-                                    NodeOccupant::Stockpile {
-                                        var: from_var.clone(),
-                                        amt: from_amt_full - from_amt,
-                                    },
-                                );
-                                autoactions.actions.push(AutoAction::ConsumeResource {
-                                    from: from_id.clone(),
-                                    to: node.id.clone(),
-                                    var: from_var.clone(),
-                                    abs: from_amt_full - from_amt,
-                                    diff: from_amt as i32,
-                                });
-                            }
-                            map.occupation.insert(
-                                node.id.clone(),
-                                NodeOccupant::Stockpile {
-                                    var: from_var.clone(),
-                                    amt: clamped,
-                                },
-                            );
-                            autoactions.actions.push(AutoAction::ProduceResource {
-                                from: node.id.clone(),
-                                to: node.id.clone(),
-                                var: from_var.clone(),
-                                abs: clamped,
-                                diff: from_amt as i32,
-                            });
-                        }
-                    } else if map.occupation.get(&node.id).is_none() {
-                        if from_amt == from_amt_full {
-                            map.occupation.remove(from_id);
-                            autoactions.actions.push(AutoAction::ConsumeResource {
-                                from: from_id.clone(),
-                                to: node.id.clone(),
-                                var: from_var.clone(),
-                                abs: 0,
-                                diff: from_amt as i32,
-                            });
+            })
+            .with_children(|row| {
+                row.spawn(AtlasImageBundle {
+                    style: Style {
+                        width: Val::Px(24.),
+                        height: Val::Px(24.),
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    texture_atlas_image: UiTextureAtlasImage {
+                        index: flight.var.get_sprite_index(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+                row.spawn(TextBundle::from_section(
+                    trf(
+                        if flight.returning {
+                            "hud.shuttle_returning"
                         } else {
-                            map.occupation.insert(
-                                from_id.clone(),
-                                NodeOccupant::Stockpile {
-                                    var: from_var.clone(),
-                                    amt: from_amt_full - from_amt,
-                                },
-                            );
-                            autoactions.actions.push(AutoAction::ConsumeResource {
-                                from: from_id.clone(),
-                                to: node.id.clone(),
-                                var: from_var.clone(),
-                                abs: from_amt_full - from_amt,
-                                diff: from_amt as i32,
-                            });
-                        }
-                        map.occupation.insert(
-                            node.id.clone(),
-                            NodeOccupant::Stockpile {
-                                var: from_var.clone(),
-                                amt: from_amt,
+                            "hud.shuttle"
+                        },
+                        &[&flight.amt, &flight.to.0, &flight.turns_left],
+                    ),
+                    small_text_style.clone(),
+                ));
+                if !flight.returning {
+                    row.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                margin: UiRect::all(Val::Px(2.)),
+                                padding: UiRect::all(Val::Px(4.)),
+                                border: UiRect::all(Val::Px(2.)),
+                                ..Default::default()
                             },
-                        );
-                        autoactions.actions.push(AutoAction::ProduceResource {
-                            from: node.id.clone(),
-                            to: node.id.clone(),
-                            var: from_var.clone(),
-                            abs: from_amt,
-                            diff: from_amt as i32,
-                        });
-                        autoactions.timer.tick(Duration::from_secs(1));
-                        event_ui.send(UiEvent::Close);
-                        return;
-                    }
+                            background_color: theme.button.into(),
+                            border_color: theme.button_border.into(),
+                            ..Default::default()
+                        },
+                        UiButton::CancelShuttle(flight.id),
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(
+                            tr("hud.shuttle_cancel"),
+                            small_text_style.clone(),
+                        ));
+                    });
                 }
-            }
-            event_ui.send(UiEvent::SelectNodeForConstruction(node.id.clone()));
+            });
         }
-    }
+    });
+}
 
-    let mut found_planet = None;
-    for (ent, node) in query_planets.iter() {
-        let tr = query_tr.get(ent).unwrap();
-        let rect = Rect::from_center_size(
-            Vec2::new(tr.translation.x, tr.translation.y),
-            Vec2::new(64., 64.),
-        );
-        if rect.contains(mouse) {
-            found_planet = Some((tr, node));
-        }
+fn ui_hud_encounters(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    encounters: Res<Encounters>,
+    query: Query<Entity, With<UiHudEncounters>>,
+) {
+    if !encounters.is_changed() {
+        return;
     }
-
-    if let Some((tr, planet)) = found_planet {
-        commands.spawn((
-            SpriteSheetBundle {
-                transform: Transform::default()
-                    .with_translation(tr.translation + Vec3::new(0., 0., 3.)),
-                sprite: TextureAtlasSprite {
-                    color: Color::WHITE.with_a(0.3),
-                    index: 5,
+    let Ok(list) = query.get_single() else {
+        return;
+    };
+    let small_text_style = theme.small(&handles);
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|list| {
+        if !encounters.active.is_empty() {
+            list.spawn(TextBundle::from_section(
+                tr("hud.encounters"),
+                theme.normal(&handles),
+            ));
+        }
+        for encounter in encounters.active.iter() {
+            list.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
                     ..Default::default()
                 },
-                texture_atlas: handles.atlas.clone(),
                 ..Default::default()
+            })
+            .with_children(|row| {
+                row.spawn(AtlasImageBundle {
+                    style: Style {
+                        width: Val::Px(24.),
+                        height: Val::Px(24.),
+                        ..Default::default()
+                    },
+                    texture_atlas: handles.atlas.clone(),
+                    texture_atlas_image: UiTextureAtlasImage {
+                        index: encounter.sprite_index(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+                row.spawn(TextBundle::from_section(
+                    trf(
+                        "hud.encounter",
+                        &[&encounter.group.0, &encounter.turns_left],
+                    ),
+                    small_text_style.clone(),
+                ));
+                row.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            margin: UiRect::all(Val::Px(2.)),
+                            padding: UiRect::all(Val::Px(4.)),
+                            border: UiRect::all(Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: theme.button_border.into(),
+                        ..Default::default()
+                    },
+                    UiButton::EncounterMenu(encounter.id),
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(
+                        tr("hud.encounter_open"),
+                        small_text_style.clone(),
+                    ));
+                });
+            });
+        }
+    });
+}
+
+#[derive(Component)]
+struct UiStats;
+
+/// the browser build has nowhere to write, it doesn't show the button
+fn export_stats(stats: &Stats) {
+    let csv = stats.to_csv();
+    #[cfg(not(target_arch = "wasm32"))]
+    match std::fs::write("stats.csv", &csv) {
+        Ok(()) => info!("statistics exported to stats.csv"),
+        Err(err) => warn!("failed to export statistics: {}", err),
+    }
+    #[cfg(target_arch = "wasm32")]
+    info!("{}", csv);
+}
+
+fn spawn_chart(
+    parent: &mut ChildBuilder,
+    title: &str,
+    series: Vec<(String, Color, Vec<u32>)>,
+    text_style: &TextStyle,
+    theme: &Theme,
+) {
+    let max = series
+        .iter()
+        .flat_map(|(_, _, values)| values.iter())
+        .max()
+        .cloned()
+        .unwrap_or(0)
+        .max(1) as f32;
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                ..default()
             },
-            Highlight,
-        ));
-        if clicked {
-            let fusion = *map
-                .get_group_bunch(&GroupId(0))
-                .res
-                .get(&ResourceVariant::FusionFuel)
-                .unwrap_or(&0);
-            if fusion > 0 && nears.contains(&planet.id) {
-                if let Ok(mut ship) = ship_q.get_single_mut() {
-                    ship.planned_move = Some(planet.id.clone());
-                    event_ui.send(UiEvent::Close);
+            ..default()
+        })
+        .with_children(|legend| {
+            legend.spawn(TextBundle::from_section(
+                trf("stats.max", &[&title, &max]),
+                text_style.clone(),
+            ));
+            for (name, color, _) in series.iter() {
+                legend.spawn(
+                    TextBundle::from_section(
+                        name.clone(),
+                        TextStyle {
+                            color: *color,
+                            ..text_style.clone()
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::horizontal(Val::Px(6.)),
+                        ..default()
+                    }),
+                );
+            }
+        });
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(18.),
+                margin: UiRect::bottom(Val::Px(10.)),
+                border: UiRect::all(Val::Px(1.)),
+                ..default()
+            },
+            border_color: theme.muted.into(),
+            ..default()
+        })
+        .with_children(|chart| {
+            for (_, color, values) in series.iter() {
+                let n = values.len().max(2) - 1;
+                // interpolate some points between turns to draw a line
+                const STEPS: usize = 6;
+                for (i, pair) in values.windows(2).enumerate() {
+                    for step in 0..STEPS {
+                        let t = step as f32 / STEPS as f32;
+                        let v = pair[0] as f32 * (1. - t) + pair[1] as f32 * t;
+                        chart.spawn(NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Percent((i as f32 + t) / n as f32 * 100.),
+                                bottom: Val::Percent(v / max * 100.),
+                                width: Val::Px(3.),
+                                height: Val::Px(3.),
+                                ..default()
+                            },
+                            background_color: (*color).into(),
+                            ..default()
+                        });
+                    }
                 }
             }
+        });
+}
+
+fn ui_stats(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut event_ui: EventReader<UiEvent>,
+    query_ui: Query<Entity, With<UiStats>>,
+    stats: Res<Stats>,
+) {
+    if !event_ui.iter().any(|e| matches!(e, UiEvent::ToggleStats)) {
+        return;
+    }
+    if !query_ui.is_empty() {
+        for e in query_ui.iter() {
+            commands.entity(e).despawn_recursive();
         }
+        return;
     }
-}
 
-#[derive(Component)]
-enum UiButton {
-    ConstructMenu(NodeId),
-    DestroyMenu(NodeId),
-    MoveMenu(NodeId, bool),
-    Construct(NodeId, ConstructionVariant),
-    EndTurn,
-}
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
 
-#[derive(Component)]
-struct UiSelectedMoveShip;
+    let totals: Vec<(String, Color, Vec<u32>)> = ResourceVariant::iter()
+        .filter(|var| var.is_stockpiled())
+        .map(|var| {
+            let values: Vec<u32> = stats
+                .turns
+                .iter()
+                .map(|t| {
+                    let ship = t.totals.get(&GroupId(0)).cloned().unwrap_or_default();
+                    *ship.res.get(&var).unwrap_or(&0)
+                })
+                .collect();
+            (var.to_string(), theme.resource_color(&var), values)
+        })
+        .collect();
+    // one line per building and resource, different goods don't add up
+    let per_building = |table: fn(&TurnStats) -> &HashMap<ConstructionVariant, Bunch>| {
+        let amount = |t: &TurnStats, constr: &ConstructionVariant, var: &ResourceVariant| {
+            table(t).get(constr).and_then(|b| b.res.get(var)).copied()
+        };
+        ConstructionVariant::iter()
+            .flat_map(|constr| ResourceVariant::iter().map(move |var| (constr.clone(), var)))
+            .filter(|(constr, var)| stats.turns.iter().any(|t| amount(t, constr, var).is_some()))
+            .zip(theme.series.iter().cycle())
+            .map(|((constr, var), color)| {
+                let values: Vec<u32> = stats
+                    .turns
+                    .iter()
+                    .map(|t| amount(t, &constr, &var).unwrap_or(0))
+                    .collect();
+                (
+                    trf("stats.series", &[&constr.to_string(), &var.to_string()]),
+                    *color,
+                    values,
+                )
+            })
+            .collect::<Vec<(String, Color, Vec<u32>)>>()
+    };
+    let produced = per_building(|t| &t.produced);
+    let consumed = per_building(|t| &t.consumed);
+    let wasted: Vec<(String, Color, Vec<u32>)> = ResourceVariant::iter()
+        .map(|var| {
+            let values: Vec<u32> = stats
+                .turns
+                .iter()
+                .map(|t| *t.wasted.res.get(&var).unwrap_or(&0))
+                .collect();
+            (var.to_string(), theme.resource_color(&var), values)
+        })
+        .collect();
 
-#[derive(Component)]
-struct UiNodeSelectedPlanet;
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(16.),
+                    top: Val::Percent(5.),
+                    width: Val::Percent(58.),
+                    height: Val::Percent(90.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            UiStats,
+            InGame,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(tr("stats.title"), big_text_style.clone()).with_style(
+                    Style {
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
+            );
+            spawn_chart(
+                root,
+                &tr("stats.inventory"),
+                totals,
+                &small_text_style,
+                &theme,
+            );
+            spawn_chart(
+                root,
+                &tr("stats.production"),
+                produced,
+                &small_text_style,
+                &theme,
+            );
+            spawn_chart(
+                root,
+                &tr("stats.consumption"),
+                consumed,
+                &small_text_style,
+                &theme,
+            );
+            spawn_chart(root, &tr("stats.wasted"), wasted, &small_text_style, &theme);
+            #[cfg(not(target_arch = "wasm32"))]
+            root.spawn((
+                ButtonBundle {
+                    style: Style {
+                        border: UiRect::all(Val::Px(3.0)),
+                        margin: UiRect::all(Val::Px(2.)),
+                        ..Default::default()
+                    },
+                    background_color: theme.button.into(),
+                    border_color: theme.button_border.into(),
+                    ..Default::default()
+                },
+                UiButton::ExportStats,
+            ))
+            .with_children(|button| {
+                button.spawn(TextBundle::from_section(
+                    tr("stats.export"),
+                    text_style.clone(),
+                ));
+            });
+        });
+}
 
 #[derive(Component)]
-struct ShipMovingTo(GroupId, Vec<GroupId>);
+struct UiResearch;
 
-fn ui_on_node_selected_planet(
+fn ui_research(
     mut commands: Commands,
     handles: Res<AssetHandles>,
+    theme: Res<Theme>,
     mut event_ui: EventReader<UiEvent>,
-    query_ui_sel: Query<(Entity, &UiNodeSelectedMove)>,
-    query_selected: Query<(Entity, &SelectedMove)>,
-    query_moving_to: Query<(Entity, &ShipMovingTo)>,
-    map: Res<Map>,
-    ship: Query<&Ship>,
+    query_ui: Query<Entity, With<UiResearch>>,
+    research: Res<Research>,
 ) {
-    if event_ui.is_empty() {
+    let toggled = event_ui
+        .iter()
+        .any(|e| matches!(e, UiEvent::ToggleResearch));
+    let open = !query_ui.is_empty();
+    // rebuild when the research changes while the screen is open
+    let rebuild = open && research.is_changed();
+    if !toggled && !rebuild {
         return;
     }
-
-    for (e, _) in query_moving_to.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for (e, _) in query_ui_sel.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for (e, _) in query_selected.iter() {
+    for e in query_ui.iter() {
         commands.entity(e).despawn_recursive();
     }
-
-    let event = event_ui
-        .iter()
-        .find(|e| matches!(e, UiEvent::SelectPlanet(_)));
-    let Some(UiEvent::SelectPlanet(group_id)) = event else {
+    if open && toggled {
         return;
-    };
-
-    if let Ok(ship) = ship.get_single() {
-        if ship.orbiting_group != *group_id {
-            return;
-        }
     }
 
-    let big_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::WHITE,
-    };
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 20.0,
-        color: Color::WHITE,
-    };
-
-    let star = map.star(&group_id);
-
-    let mut nears = vec![];
-    for neighbor_group_id in star.iter() {
-        if neighbor_group_id == &GroupId(0) {
-            // that's a ship not a planet
-            continue;
-        }
-        nears.push(neighbor_group_id.clone());
-        let pos = map.group_positions.get(neighbor_group_id).unwrap();
-        commands.spawn((
-            SpriteSheetBundle {
-                transform: Transform::default().with_translation(pos.extend(4.)),
-                sprite: TextureAtlasSprite {
-                    index: 5,
-                    ..Default::default()
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(16.),
+                    top: Val::Percent(5.),
+                    width: Val::Percent(58.),
+                    border: UiRect::all(Val::Px(5.0)),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
                 },
-                texture_atlas: handles.atlas.clone(),
-                ..Default::default()
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                z_index: ZIndex::Global(5),
+                ..default()
             },
-            SelectedMove,
-        ));
+            UiResearch,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(tr("research.title"), big_text_style.clone()).with_style(
+                    Style {
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
+            );
+            root.spawn(TextBundle::from_section(
+                tr("research.help"),
+                small_text_style.clone(),
+            ));
+            for project in techs().iter() {
+                let progress = *research.progress.get(&project.id).unwrap_or(&0);
+                let (status, color) = if research.done.contains(&project.id) {
+                    (tr("research.done_status"), theme.positive)
+                } else if research.current == Some(project.id) {
+                    (
+                        trf("research.current", &[&progress, &project.cost]),
+                        theme.focus,
+                    )
+                } else if research.available(project) {
+                    (
+                        trf("research.available", &[&progress, &project.cost]),
+                        theme.text,
+                    )
+                } else {
+                    let missing: Vec<String> = project
+                        .requires
+                        .iter()
+                        .filter(|id| !research.done.contains(id))
+                        .map(|id| tr(&tech(*id).name))
+                        .collect();
+                    (
+                        trf("research.locked", &[&missing.join(", ")]),
+                        theme.negative,
+                    )
+                };
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            border: UiRect::all(Val::Px(3.0)),
+                            margin: UiRect::all(Val::Px(2.)),
+                            padding: UiRect::all(Val::Px(4.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: if research.current == Some(project.id) {
+                            theme.focus.into()
+                        } else {
+                            theme.button_border.into()
+                        },
+                        ..Default::default()
+                    },
+                    UiButton::PickResearch(project.id),
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(
+                        tr(&project.name),
+                        text_style.clone(),
+                    ));
+                    button.spawn(TextBundle::from_section(
+                        project.effect.describe(),
+                        small_text_style.clone(),
+                    ));
+                    button.spawn(TextBundle::from_section(
+                        status,
+                        TextStyle {
+                            color,
+                            ..small_text_style.clone()
+                        },
+                    ));
+                });
+            }
+        });
+}
+
+/// open lanes are drawn on the map, closed ones are crossed out
+fn draw_lanes(mut gizmos: Gizmos, map: Res<Map>, theme: Res<Theme>) {
+    for (a, b) in map.lanes.iter() {
+        let (Some(from), Some(to)) = (map.group_positions.get(a), map.group_positions.get(b))
+        else {
+            continue;
+        };
+        if map.edges.contains(&(a.clone(), b.clone())) {
+            gizmos.line_2d(*from, *to, theme.positive.with_a(0.5));
+            continue;
+        }
+        let mid = (*from + *to) / 2.;
+        let dir = (*to - *from).normalize_or_zero() * 12.;
+        gizmos.line_2d(*from, *to, theme.negative.with_a(0.5));
+        gizmos.line_2d(
+            mid - dir - dir.perp(),
+            mid + dir + dir.perp(),
+            theme.negative,
+        );
+        gizmos.line_2d(
+            mid - dir + dir.perp(),
+            mid + dir - dir.perp(),
+            theme.negative,
+        );
     }
+}
 
-    commands.spawn(ShipMovingTo(group_id.clone(), nears));
+#[derive(Component)]
+struct UiOrbits;
+
+fn ui_orbits(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    theme: Res<Theme>,
+    mut event_ui: EventReader<UiEvent>,
+    query_ui: Query<Entity, With<UiOrbits>>,
+    map: Res<Map>,
+    turns: Res<TurnCount>,
+) {
+    let toggled = event_ui.iter().any(|e| matches!(e, UiEvent::ToggleOrbits));
+    let open = !query_ui.is_empty();
+    // the planets move every turn
+    let rebuild = open && turns.is_changed();
+    if !toggled && !rebuild {
+        return;
+    }
+    for e in query_ui.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    if open && toggled {
+        return;
+    }
 
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
+    let size = 220.;
+    let widest = map
+        .orbits
+        .values()
+        .map(|orbit| orbit.radius)
+        .fold(1., f32::max);
     commands
         .spawn((
             NodeBundle {
                 style: Style {
                     position_type: PositionType::Absolute,
-                    bottom: Val::Percent(0.),
-                    right: Val::Percent(0.),
-                    width: Val::Percent(25.),
-                    height: Val::Percent(100.),
+                    left: Val::Percent(16.),
+                    top: Val::Percent(5.),
+                    width: Val::Percent(58.),
                     border: UiRect::all(Val::Px(5.0)),
+                    padding: UiRect::all(Val::Px(10.0)),
                     flex_direction: FlexDirection::Column,
                     ..default()
                 },
-                background_color: Color::rgb(0.1, 0.1, 0.1).into(),
-                border_color: Color::WHITE.into(),
+                background_color: theme.panel.into(),
+                border_color: theme.border.into(),
+                z_index: ZIndex::Global(5),
                 ..default()
             },
-            UiNodeSelectedMove,
+            UiOrbits,
         ))
         .with_children(|root| {
-            let fusion = *map
-                .get_group_bunch(&GroupId(0))
-                .res
-                .get(&ResourceVariant::FusionFuel)
-                .unwrap_or(&0);
             root.spawn(
-                TextBundle::from_section(
-                    format!(
-                        "Select a destination.\nTraveling will use 1 Fusion Fuel.\nIn the ship there is {} Fusion Fuel.",
-                        fusion,
-                    ),
-                    text_style.clone(),
-                )
-                .with_style(Style {
-                    position_type: PositionType::Relative,
-                    margin: UiRect::vertical(Val::Px(10.)),
-                    ..default()
-                }),
+                TextBundle::from_section(tr("orbits.title"), big_text_style.clone()).with_style(
+                    Style {
+                        margin: UiRect::bottom(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
             );
+            root.spawn(TextBundle::from_section(
+                trf("orbits.help", &[&TRANSFER_WINDOW]),
+                small_text_style.clone(),
+            ));
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    margin: UiRect::top(Val::Px(10.)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                // top-down view of the system, the star in the middle
+                row.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(size),
+                        height: Val::Px(size),
+                        flex_shrink: 0.,
+                        margin: UiRect::right(Val::Px(10.)),
+                        ..default()
+                    },
+                    background_color: Color::BLACK.into(),
+                    ..default()
+                })
+                .with_children(|orrery| {
+                    let mut bodies: Vec<(String, Vec2, f32, Color)> =
+                        vec![("".to_string(), Vec2::ZERO, 12., Color::YELLOW)];
+                    for (group_id, orbit) in map.orbits.iter() {
+                        let pos = orbit.position(map.turn) / widest * (size / 2. - 14.);
+                        bodies.push((group_id.0.to_string(), pos, 8., theme.text));
+                    }
+                    for (label, pos, dot, color) in bodies {
+                        orrery
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    left: Val::Px(size / 2. + pos.x - dot / 2.),
+                                    top: Val::Px(size / 2. - pos.y - dot / 2.),
+                                    width: Val::Px(dot),
+                                    height: Val::Px(dot),
+                                    ..default()
+                                },
+                                background_color: color.into(),
+                                ..default()
+                            })
+                            .with_children(|body| {
+                                body.spawn(
+                                    TextBundle::from_section(label, small_text_style.clone())
+                                        .with_style(Style {
+                                            position_type: PositionType::Absolute,
+                                            left: Val::Px(dot + 2.),
+                                            ..default()
+                                        }),
+                                );
+                            });
+                    }
+                });
+                row.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|table| {
+                    for (a, b) in map.lanes.iter() {
+                        let status = match map.next_window(a, b, TIMELINE_TURNS) {
+                            Some((0, length)) if length == TIMELINE_TURNS => {
+                                trf("orbits.open_long", &[&map.jump_cost(a, b), &length])
+                            }
+                            Some((0, length)) => {
+                                trf("orbits.open", &[&map.jump_cost(a, b), &length])
+                            }
+                            Some((start, _)) => trf("orbits.opens", &[&start]),
+                            None => trf("orbits.closed", &[&TIMELINE_TURNS]),
+                        };
+                        table
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Row,
+                                    align_items: AlignItems::Center,
+                                    margin: UiRect::bottom(Val::Px(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|lane| {
+                                lane.spawn(
+                                    TextBundle::from_section(
+                                        trf("orbits.lane", &[&a.0, &b.0]),
+                                        text_style.clone(),
+                                    )
+                                    .with_style(Style {
+                                        width: Val::Px(90.),
+                                        ..default()
+                                    }),
+                                );
+                                // one cell per turn, starting from the current one
+                                for t in 0..TIMELINE_TURNS {
+                                    let open = map.lane_open_at(a, b, map.turn + t);
+                                    lane.spawn(NodeBundle {
+                                        style: Style {
+                                            width: Val::Px(8.),
+                                            height: Val::Px(12.),
+                                            margin: UiRect::right(Val::Px(1.)),
+                                            ..default()
+                                        },
+                                        background_color: if open {
+                                            theme.positive.into()
+                                        } else {
+                                            theme.muted.into()
+                                        },
+                                        ..default()
+                                    });
+                                }
+                                lane.spawn(
+                                    TextBundle::from_section(status, small_text_style.clone())
+                                        .with_style(Style {
+                                            margin: UiRect::left(Val::Px(6.)),
+                                            ..default()
+                                        }),
+                                );
+                            });
+                    }
+                });
+            });
         });
 }
 
-fn move_hotkeys(
-    keys: Res<Input<KeyCode>>,
-    q: Query<&UiCanHotkey>,
-    mut event_ui: EventWriter<UiEvent>,
-) {
-    if let Ok(UiCanHotkey(id)) = q.get_single() {
-        if keys.just_pressed(KeyCode::A) {
-            event_ui.send(UiEvent::SelectNodeForMove(id.clone(), false));
-        }
-        if keys.just_pressed(KeyCode::S) {
-            event_ui.send(UiEvent::SelectNodeForMove(id.clone(), true));
-        }
-    }
-}
-
 #[derive(Component)]
-struct UiNodeSelectedMove;
+struct UiMainMenu;
 
 #[derive(Component)]
-struct UiCanHotkey(NodeId);
+enum UiMenuButton {
+    Play,
+    Tutorial,
+    Language,
+    Palette,
+    Scale,
+    Speed,
+    Achievements,
+    Campaign,
+    Jump(&'static str),
+    NewCampaign,
+    Back,
+}
 
+/// everything spawned for a game, despawned when going back to the menu
 #[derive(Component)]
-struct MovingTo(NodeId, Vec<NodeId>, bool);
+struct InGame;
 
-fn ui_on_node_selected_move(
+/// wipe the previous game so that it can be started again from the menu
+fn reset_game(mut commands: Commands, query: Query<Entity, With<InGame>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.insert_resource(Map::test());
+    commands.insert_resource(AutoActions::default());
+    commands.insert_resource(TurnCount::default());
+    commands.insert_resource(Hover::default());
+    commands.insert_resource(ShipNet::default());
+    commands.insert_resource(Stats::default());
+    commands.insert_resource(NavCursor::default());
+    commands.insert_resource(Crew::default());
+    commands.insert_resource(Research::default());
+    commands.insert_resource(PowerGrid::default());
+    commands.insert_resource(Shuttles::default());
+    commands.insert_resource(Encounters::default());
+    commands.insert_resource(Scenario::default());
+}
+
+/// a campaign game goes back to the star map
+fn ui_main_menu(
     mut commands: Commands,
     handles: Res<AssetHandles>,
-    mut event_ui: EventReader<UiEvent>,
-    query_ui_sel: Query<(Entity, &UiNodeSelectedMove)>,
-    query_selected: Query<(Entity, &SelectedMove)>,
-    query_moving_to: Query<(Entity, &MovingTo)>,
-    map: Res<Map>,
+    theme: Res<Theme>,
+    campaign: Res<Campaign>,
+    speed: Res<AnimSpeed>,
 ) {
-    if event_ui.is_empty() {
-        return;
+    if campaign.current.is_some() {
+        spawn_star_map(&mut commands, &handles, &theme, &campaign);
+    } else {
+        spawn_main_menu(&mut commands, &handles, &theme, &speed);
     }
+}
 
-    for (e, _) in query_moving_to.iter() {
-        commands.entity(e).despawn_recursive();
+fn campaign_win(map: Res<Map>, research: Res<Research>, mut campaign: ResMut<Campaign>) {
+    if campaign.current.is_some() {
+        campaign.win(&map, &research);
+        campaign.save();
     }
-    for (e, _) in query_ui_sel.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for (e, _) in query_selected.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-
-    let event = event_ui
-        .iter()
-        .find(|e| matches!(e, UiEvent::SelectNodeForMove(_, _)));
-    let Some(UiEvent::SelectNodeForMove(id, split)) = event else {
-        return;
-    };
-
-    let big_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::WHITE,
-    };
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 20.0,
-        color: Color::WHITE,
-    };
-
-    let mut nears = vec![];
+}
 
-    let group_id = map.group_from_node(id);
-    let mut star: Vec<GroupId> = map
-        .star(&group_id)
-        .iter()
-        .filter(|neigh_id| {
-            if group_id == GroupId(0) {
-                true
-            } else {
-                neigh_id == &&GroupId(0)
-            }
-        })
-        .cloned()
-        .collect();
-    star.push(group_id.clone());
-    for neighbor_group_id in star.iter() {
-        for node_id in map.groups.get(neighbor_group_id).unwrap().iter() {
-            nears.push(node_id.clone());
-            let pos = map.positions.get(node_id).unwrap();
-            // if planet, red
-            commands.spawn((
-                SpriteSheetBundle {
-                    transform: Transform::default().with_translation(pos.extend(4.)),
-                    sprite: TextureAtlasSprite {
-                        index: 2,
+fn spawn_main_menu(
+    commands: &mut Commands,
+    handles: &AssetHandles,
+    theme: &Theme,
+    speed: &AnimSpeed,
+) {
+    let big_text_style = theme.big(handles);
+    let text_style = theme.normal(handles);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: theme.panel.into(),
+                ..default()
+            },
+            UiMainMenu,
+        ))
+        .with_children(|root| {
+            root.spawn(
+                TextBundle::from_section(tr("menu.title"), big_text_style.clone()).with_style(
+                    Style {
+                        margin: UiRect::bottom(Val::Px(20.)),
+                        ..default()
+                    },
+                ),
+            );
+            for (label, button) in [
+                (tr("menu.play"), UiMenuButton::Play),
+                (tr("menu.campaign"), UiMenuButton::Campaign),
+                (tr("menu.tutorial"), UiMenuButton::Tutorial),
+                (
+                    trf("menu.language", &[&language().name()]),
+                    UiMenuButton::Language,
+                ),
+                (
+                    trf("menu.palette", &[&theme.palette.to_string()]),
+                    UiMenuButton::Palette,
+                ),
+                (
+                    trf("menu.scale", &[&(theme.scale * 100.).round()]),
+                    UiMenuButton::Scale,
+                ),
+                (
+                    trf("menu.speed", &[&speed.to_string()]),
+                    UiMenuButton::Speed,
+                ),
+                (tr("menu.achievements"), UiMenuButton::Achievements),
+            ] {
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            border: UiRect::all(Val::Px(3.0)),
+                            margin: UiRect::all(Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        background_color: theme.button.into(),
+                        border_color: theme.button_border.into(),
                         ..Default::default()
                     },
-                    texture_atlas: handles.atlas.clone(),
-                    ..Default::default()
-                },
-                SelectedMove,
-            ));
-        }
-    }
-
-    commands.spawn(MovingTo(id.clone(), nears, *split));
+                    button,
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(label, text_style.clone()));
+                });
+            }
+        });
+}
 
+/// every achievement, the locked ones dimmed
+fn spawn_gallery(
+    commands: &mut Commands,
+    handles: &AssetHandles,
+    theme: &Theme,
+    profile: &Profile,
+) {
+    let big_text_style = theme.big(handles);
+    let text_style = theme.normal(handles);
+    let small_text_style = theme.small(handles);
+    let unlocked = ACHIEVEMENTS
+        .iter()
+        .filter(|a| profile.unlocked(a.id))
+        .count();
     commands
         .spawn((
             NodeBundle {
                 style: Style {
                     position_type: PositionType::Absolute,
-                    bottom: Val::Percent(0.),
-                    right: Val::Percent(0.),
-                    width: Val::Percent(25.),
+                    width: Val::Percent(100.),
                     height: Val::Percent(100.),
-                    border: UiRect::all(Val::Px(5.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
                     flex_direction: FlexDirection::Column,
                     ..default()
                 },
-                background_color: Color::rgb(0.1, 0.1, 0.1).into(),
-                border_color: Color::WHITE.into(),
+                background_color: theme.panel.into(),
                 ..default()
             },
-            UiNodeSelectedMove,
+            UiMainMenu,
         ))
         .with_children(|root| {
             root.spawn(
-                TextBundle::from_section("Select a destination", big_text_style.clone())
-                    .with_style(Style {
-                        position_type: PositionType::Relative,
-                        top: Val::Percent(0.),
-                        right: Val::Percent(0.),
-                        margin: UiRect::bottom(Val::Px(10.)),
-                        ..default()
-                    }),
+                TextBundle::from_section(
+                    trf("gallery.title", &[&unlocked, &ACHIEVEMENTS.len()]),
+                    big_text_style.clone(),
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                }),
             );
+            for achievement in ACHIEVEMENTS.iter() {
+                let color = if profile.unlocked(achievement.id) {
+                    theme.positive
+                } else {
+                    theme.muted
+                };
+                root.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::all(Val::Px(6.)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|entry| {
+                    entry.spawn(TextBundle::from_section(
+                        tr(achievement.name),
+                        TextStyle {
+                            color,
+                            ..text_style.clone()
+                        },
+                    ));
+                    entry.spawn(TextBundle::from_section(
+                        tr(achievement.desc),
+                        small_text_style.clone(),
+                    ));
+                });
+            }
+            root.spawn((
+                ButtonBundle {
+                    style: Style {
+                        border: UiRect::all(Val::Px(3.0)),
+                        margin: UiRect::top(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                    background_color: theme.button.into(),
+                    border_color: theme.button_border.into(),
+                    ..Default::default()
+                },
+                UiMenuButton::Back,
+            ))
+            .with_children(|button| {
+                button.spawn(TextBundle::from_section(
+                    tr("menu.back"),
+                    text_style.clone(),
+                ));
+            });
         });
 }
 
-#[derive(Component)]
-struct UiNodeSelectedConstr;
-
-fn ui_on_node_selected_constr(
-    mut commands: Commands,
-    handles: Res<AssetHandles>,
-    mut event_ui: EventReader<UiEvent>,
-    query_ui_sel: Query<(Entity, &UiNodeSelectedConstr)>,
-    query_selected: Query<(Entity, &Selected)>,
-    query_hot: Query<(Entity, &UiCanHotkey)>,
-    map: Res<Map>,
+/// every system where the star map says it is, won ones in green and locked ones dimmed
+fn spawn_star_map(
+    commands: &mut Commands,
+    handles: &AssetHandles,
+    theme: &Theme,
+    campaign: &Campaign,
 ) {
-    if event_ui.is_empty() {
-        return;
-    }
-
-    for (e, _) in query_hot.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for (e, _) in query_ui_sel.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for (e, _) in query_selected.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-
-    let event = event_ui
-        .iter()
-        .find(|e| matches!(e, UiEvent::SelectNodeForConstruction(_)));
-    let Some(UiEvent::SelectNodeForConstruction(id)) = event else {
-        return;
-    };
-
-    commands.spawn(UiCanHotkey(id.clone()));
-
-    let big_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::WHITE,
-    };
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 20.0,
-        color: Color::WHITE,
-    };
-    let small_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 13.0,
-        color: Color::WHITE,
+    let big_text_style = theme.big(handles);
+    let text_style = theme.normal(handles);
+    let small_text_style = theme.small(handles);
+    let systems = StarSystem::all();
+    let reachable = campaign.reachable(&systems);
+    let name = |id: &str| {
+        systems
+            .iter()
+            .find(|system| system.id == id)
+            .map_or(id.to_string(), |system| tr(&system.name))
     };
-
-    let pos = map.positions.get(id).unwrap();
-    commands.spawn((
-        SpriteSheetBundle {
-            transform: Transform::default().with_translation(pos.extend(4.)),
-            sprite: TextureAtlasSprite {
-                index: 2,
-                ..Default::default()
-            },
-            texture_atlas: handles.atlas.clone(),
-            ..Default::default()
-        },
-        Selected,
-    ));
-
     commands
         .spawn((
             NodeBundle {
                 style: Style {
                     position_type: PositionType::Absolute,
-                    bottom: Val::Percent(0.),
-                    right: Val::Percent(0.),
-                    width: Val::Percent(25.),
+                    width: Val::Percent(100.),
                     height: Val::Percent(100.),
-                    border: UiRect::all(Val::Px(5.0)),
+                    align_items: AlignItems::Center,
                     flex_direction: FlexDirection::Column,
                     ..default()
                 },
-                background_color: Color::rgb(0.1, 0.1, 0.1).into(),
-                border_color: Color::WHITE.into(),
+                background_color: theme.panel.into(),
                 ..default()
             },
-            UiNodeSelectedConstr,
+            UiMainMenu,
         ))
         .with_children(|root| {
             root.spawn(
-                TextBundle::from_section("Available Actions", big_text_style.clone()).with_style(
+                TextBundle::from_section(tr("starmap.title"), big_text_style.clone()).with_style(
                     Style {
-                        position_type: PositionType::Relative,
-                        top: Val::Percent(0.),
-                        right: Val::Percent(0.),
-                        margin: UiRect::bottom(Val::Px(10.)),
+                        margin: UiRect::all(Val::Px(20.)),
                         ..default()
                     },
                 ),
             );
-            let group_id = map.group_from_node(id);
-            let is_ship_present =
-                group_id == GroupId(0) || map.star(&GroupId(0)).contains(&group_id);
-            if !is_ship_present {
-                root.spawn(
-                    TextBundle::from_section(
-                        "Your ship is too far away from this location.\nYou can move the ship\
-                        closer if you have 1 Fusion Fuel.",
-                        text_style.clone(),
-                    )
-                    .with_style(Style {
-                        margin: UiRect::bottom(Val::Px(10.)),
+            root.spawn(TextBundle::from_section(
+                if reachable.is_empty() {
+                    tr("starmap.done")
+                } else {
+                    trf("starmap.cargo", &[&campaign.cargo.describe()])
+                },
+                text_style.clone(),
+            ));
+            for system in systems.iter() {
+                let won = campaign.won.iter().any(|id| id == system.id);
+                let open = reachable.contains(&system.id);
+                let color = if won {
+                    theme.positive
+                } else if open {
+                    theme.text
+                } else {
+                    theme.muted
+                };
+                let mut entry = root.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(system.position.x * 100. - 10.),
+                        top: Val::Percent(system.position.y * 100.),
+                        width: Val::Percent(20.),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
                         ..default()
-                    }),
-                );
-                return;
-            }
-            let occ = map.occupation.get(id);
-            if occ.is_none() {
-                root.spawn((
-                    ButtonBundle {
-                        style: Style {
-                            flex_direction: FlexDirection::Row,
-                            border: UiRect::all(Val::Px(3.0)),
-                            margin: UiRect::all(Val::Px(2.)),
-                            ..Default::default()
-                        },
-                        background_color: Color::rgb(0.14, 0.14, 0.14).into(),
-                        border_color: Color::rgb(0.2, 0.2, 0.2).into(),
-                        ..Default::default()
                     },
-                    UiButton::ConstructMenu(id.clone()),
-                ))
-                .with_children(|button| {
-                    button.spawn(
-                        TextBundle::from_section("Construct", big_text_style.clone()).with_style(
-                            Style {
-                                position_type: PositionType::Relative,
-                                ..default()
-                            },
-                        ),
-                    );
+                    ..default()
                 });
-            } else if let Some(NodeOccupant::Construction { var, cooldown }) = occ {
-                root.spawn(
-                    TextBundle::from_section(format!("{}", var.to_string()), text_style.clone())
-                        .with_style(Style {
-                            position_type: PositionType::Relative,
-                            ..default()
-                        }),
-                );
-                root.spawn(
-                    TextBundle::from_section(
-                        format!("Will produce in {} turns", cooldown),
-                        text_style.clone(),
-                    )
-                    .with_style(Style {
-                        position_type: PositionType::Relative,
-                        ..default()
-                    }),
-                );
-                root.spawn((
-                    ButtonBundle {
-                        style: Style {
-                            flex_direction: FlexDirection::Row,
-                            border: UiRect::all(Val::Px(3.0)),
-                            margin: UiRect::all(Val::Px(2.)),
-                            ..Default::default()
+                if open {
+                    entry.insert((
+                        Button,
+                        Interaction::default(),
+                        BorderColor(theme.button_border),
+                        BackgroundColor(theme.button),
+                        UiMenuButton::Jump(system.id),
+                    ));
+                }
+                entry.with_children(|entry| {
+                    entry.spawn(TextBundle::from_section(
+                        tr(&system.name),
+                        TextStyle {
+                            color,
+                            ..text_style.clone()
                         },
-                        background_color: Color::rgb(0.14, 0.14, 0.14).into(),
-                        border_color: Color::rgb(0.2, 0.2, 0.2).into(),
-                        ..Default::default()
-                    },
-                    UiButton::DestroyMenu(id.clone()),
-                ))
-                .with_children(|button| {
-                    button.spawn(
-                        TextBundle::from_section("Demolish", big_text_style.clone()).with_style(
-                            Style {
-                                position_type: PositionType::Relative,
-                                ..default()
-                            },
-                        ),
-                    );
+                    ));
+                    entry.spawn(TextBundle::from_section(
+                        trf("starmap.modifiers", &[&system.sunlight, &system.reserves]),
+                        small_text_style.clone(),
+                    ));
+                    if !system.next.is_empty() {
+                        entry.spawn(TextBundle::from_section(
+                            trf(
+                                "starmap.next",
+                                &[&system
+                                    .next
+                                    .iter()
+                                    .map(|id| name(id))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")],
+                            ),
+                            small_text_style.clone(),
+                        ));
+                    }
                 });
-            } else if let Some(NodeOccupant::Stockpile { var, amt }) = occ {
-                root.spawn(
-                    TextBundle::from_section(
-                        format!("Stockpile of {} {}", amt, var.to_string()),
-                        text_style.clone(),
-                    )
-                    .with_style(Style {
-                        position_type: PositionType::Relative,
-                        ..default()
-                    }),
-                );
-                root.spawn((
-                    ButtonBundle {
-                        style: Style {
-                            flex_direction: FlexDirection::Column,
-                            border: UiRect::all(Val::Px(3.0)),
-                            margin: UiRect::all(Val::Px(2.)),
-                            ..Default::default()
-                        },
-                        background_color: Color::rgb(0.14, 0.14, 0.14).into(),
-                        border_color: Color::rgb(0.2, 0.2, 0.2).into(),
-                        ..Default::default()
-                    },
-                    UiButton::MoveMenu(id.clone(), false),
-                ))
-                .with_children(|button| {
-                    button.spawn(
-                        TextBundle::from_section("Move All", big_text_style.clone()).with_style(
-                            Style {
-                                position_type: PositionType::Relative,
-                                ..default()
+            }
+            root.spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.),
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                for (label, button) in [
+                    ("starmap.new", UiMenuButton::NewCampaign),
+                    ("menu.back", UiMenuButton::Back),
+                ] {
+                    row.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                border: UiRect::all(Val::Px(3.0)),
+                                margin: UiRect::all(Val::Px(2.)),
+                                ..Default::default()
                             },
-                        ),
-                    );
-                    button.spawn(TextBundle::from_section("Hotkey: a", text_style.clone()));
-                });
-                root.spawn((
-                    ButtonBundle {
-                        style: Style {
-                            flex_direction: FlexDirection::Column,
-                            border: UiRect::all(Val::Px(3.0)),
-                            margin: UiRect::all(Val::Px(2.)),
+                            background_color: theme.button.into(),
+                            border_color: theme.button_border.into(),
                             ..Default::default()
                         },
-                        background_color: Color::rgb(0.14, 0.14, 0.14).into(),
-                        border_color: Color::rgb(0.2, 0.2, 0.2).into(),
-                        ..Default::default()
-                    },
-                    UiButton::MoveMenu(id.clone(), true),
-                ))
-                .with_children(|button| {
-                    button.spawn(
-                        TextBundle::from_section("Move Half", big_text_style.clone()).with_style(
-                            Style {
-                                position_type: PositionType::Relative,
-                                ..default()
-                            },
-                        ),
-                    );
-                    button.spawn(TextBundle::from_section("Hotkey: s", text_style.clone()));
-                });
-            }
+                        button,
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(tr(label), text_style.clone()));
+                    });
+                }
+            });
         });
 }
 
-fn button_system(
+fn despawn_main_menu(mut commands: Commands, query: Query<Entity, With<UiMainMenu>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn menu_buttons(
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &UiButton),
+        (&Interaction, &mut BackgroundColor, &UiMenuButton),
         (Changed<Interaction>, With<Button>),
     >,
-    mut event_ui: EventWriter<UiEvent>,
-    mut event_construct: EventWriter<BuildConstruction>,
-    mut event_destruct: EventWriter<DestroyConstruction>,
-    mut events_end: EventWriter<EndTurn>,
-    mut map: ResMut<Map>,
-    mut autoactions: ResMut<AutoActions>,
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    query_menu: Query<Entity, With<UiMainMenu>>,
+    mut theme: ResMut<Theme>,
+    mut tutorial: ResMut<Tutorial>,
+    mut next_state: ResMut<NextState<AppState>>,
+    time: Res<Time>,
+    profile: Res<Profile>,
+    mut campaign: ResMut<Campaign>,
+    mut speed: ResMut<AnimSpeed>,
 ) {
-    for (interaction, mut color, ui_button) in &mut interaction_query {
+    for (interaction, mut color, button) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                *color = Color::RED.into();
-                match ui_button {
-                    UiButton::ConstructMenu(id) => {
-                        event_ui.send(UiEvent::ConstructOnNode(id.clone()));
+                *color = theme.button_pressed.into();
+                match button {
+                    UiMenuButton::Language => set_language(language().next()),
+                    UiMenuButton::Palette => *theme = Theme::new(theme.palette.next(), theme.scale),
+                    UiMenuButton::Scale => {
+                        let scale = match theme.scale {
+                            s if s < 1. => 1.,
+                            s if s < 1.25 => 1.25,
+                            s if s < 1.5 => 1.5,
+                            _ => 0.75,
+                        };
+                        *theme = Theme::new(theme.palette, scale);
                     }
-                    UiButton::Construct(node_id, var) => {
-                        let group_id = map.group_from_node(node_id);
-                        let cash = *map
-                            .get_group_bunch(&group_id)
-                            .res
-                            .get(&ResourceVariant::Material)
-                            .clone()
-                            .unwrap_or(&0);
-                        let can_buy = cash >= var.get_material_cost();
-                        if map.occupation.get(node_id).is_none() && can_buy {
-                            let occ = NodeOccupant::Construction {
-                                var: var.clone(),
-                                cooldown: 0,
-                            };
-                            map.set_at(node_id, occ);
-                            event_construct.send(BuildConstruction {
-                                node_id: node_id.clone(),
-                                var: var.clone(),
-                            });
-                            event_ui.send(UiEvent::SelectNodeForConstruction(node_id.clone()));
-                            let group_id = map.group_from_node(node_id);
-                            let requested =
-                                Bunch::single(ResourceVariant::Material, var.get_material_cost());
-                            for (var, amt) in requested.res.iter() {
-                                let mut left = amt.clone();
-                                for _j in 0..16 {
-                                    if left == 0 {
-                                        break;
-                                    }
-                                    // delete from the lowest stockpile
-                                    let lowest_id = map.get_lowest_stockpile(&group_id, var);
-                                    let NodeOccupant::Stockpile { amt: stock_amt, .. } =
-                                        map.occupation.get_mut(&lowest_id).expect("no lowest")
-                                    else {
-                                        panic!("lowest isn't a stockpile")
-                                    };
-                                    let clamped = left.min(*stock_amt);
-                                    autoactions.actions.push(AutoAction::ConsumeResource {
-                                        from: lowest_id.clone(),
-                                        to: node_id.clone(),
-                                        var: var.clone(),
-                                        abs: *stock_amt - clamped,
-                                        diff: *amt as i32,
-                                    });
-                                    *stock_amt -= clamped;
-                                    if *stock_amt == 0 {
-                                        map.occupation.remove(&lowest_id);
-                                    }
-                                    left -= clamped;
-                                }
-                            }
-                            autoactions.timer.tick(Duration::from_secs(1));
+                    UiMenuButton::Speed => *speed = speed.next(),
+                    UiMenuButton::Play | UiMenuButton::Tutorial => {
+                        campaign.current = None;
+                        *tutorial = Tutorial {
+                            active: matches!(button, UiMenuButton::Tutorial),
+                            step: 0,
+                        };
+                        // every game meets different traders
+                        commands.insert_resource(Encounters::new(time.elapsed().as_nanos() as u64));
+                        next_state.set(AppState::Setup);
+                        continue;
+                    }
+                    UiMenuButton::Achievements => {
+                        for e in query_menu.iter() {
+                            commands.entity(e).despawn_recursive();
                         }
+                        spawn_gallery(&mut commands, &handles, &theme, &profile);
+                        continue;
                     }
-                    UiButton::DestroyMenu(node_id) => {
-                        map.occupation.remove(node_id);
-                        event_destruct.send(DestroyConstruction {
-                            node_id: node_id.clone(),
-                        });
-                        event_ui.send(UiEvent::Close);
+                    UiMenuButton::Campaign | UiMenuButton::NewCampaign => {
+                        if matches!(button, UiMenuButton::NewCampaign) {
+                            *campaign = Campaign::default();
+                            campaign.save();
+                        }
+                        for e in query_menu.iter() {
+                            commands.entity(e).despawn_recursive();
+                        }
+                        spawn_star_map(&mut commands, &handles, &theme, &campaign);
+                        continue;
                     }
-                    UiButton::MoveMenu(node_id, split) => {
-                        event_ui.send(UiEvent::SelectNodeForMove(node_id.clone(), *split));
+                    UiMenuButton::Jump(id) => {
+                        let Some(system) = StarSystem::all().into_iter().find(|s| s.id == *id)
+                        else {
+                            continue;
+                        };
+                        commands.insert_resource(system.map());
+                        commands.insert_resource(system.scenario(&campaign.cargo));
+                        commands.insert_resource(campaign.research.clone());
+                        commands.insert_resource(Encounters::new(time.elapsed().as_nanos() as u64));
+                        campaign.current = Some(system.id.to_string());
+                        tutorial.active = false;
+                        next_state.set(AppState::Setup);
+                        continue;
                     }
-                    UiButton::EndTurn => {
-                        events_end.send(EndTurn);
+                    UiMenuButton::Back => {
+                        campaign.current = None;
                     }
                 }
+                // rebuild the menu with the new settings
+                for e in query_menu.iter() {
+                    commands.entity(e).despawn_recursive();
+                }
+                spawn_main_menu(&mut commands, &handles, &theme, &speed);
             }
             Interaction::Hovered => {
-                *color = Color::BLACK.with_a(0.3).into();
+                *color = theme.button_hovered.into();
             }
             Interaction::None => {
-                *color = Color::BLACK.into();
+                *color = theme.button.into();
             }
         }
     }
 }
 
+#[derive(Resource, Clone, Debug, Default)]
+struct Tutorial {
+    active: bool,
+    step: usize,
+}
+
+/// what the tutorial points at
+enum TutorialFocus {
+    Nothing,
+    Node(usize),
+    Planet(usize),
+    MoveMenu,
+    ConstructMenu,
+    EndTurn,
+}
+
+/// the word and the number that follows it, as written in the tutorial file
+fn word_and_number(text: &str) -> Option<(&str, Option<usize>)> {
+    let mut words = text.split_whitespace();
+    let word = words.next()?;
+    Some((word, words.next().and_then(|n| n.parse().ok())))
+}
+
+impl TutorialFocus {
+    fn parse(text: &str) -> Option<Self> {
+        Some(match word_and_number(text)? {
+            ("nothing", _) => Self::Nothing,
+            ("node", Some(n)) => Self::Node(n),
+            ("planet", Some(n)) => Self::Planet(n),
+            ("move_menu", _) => Self::MoveMenu,
+            ("construct_menu", _) => Self::ConstructMenu,
+            ("end_turn", _) => Self::EndTurn,
+            _ => return None,
+        })
+    }
+
+    fn is_button(&self, button: &UiButton) -> bool {
+        matches!(
+            (self, button),
+            (Self::MoveMenu, UiButton::MoveMenu(_, false))
+                | (Self::ConstructMenu, UiButton::ConstructMenu(_))
+                | (Self::EndTurn, UiButton::EndTurn)
+        )
+    }
+}
+
+/// what the tutorial waits for before going to the next step
+enum TutorialTrigger {
+    SelectNode(usize),
+    OpenMoveMenu,
+    OpenConstructMenu,
+    StockpileInGroup(usize),
+    BuildingInGroup(usize),
+    Turn(u32),
+    PlanShipMove,
+    Continue,
+}
+
+impl TutorialTrigger {
+    fn parse(text: &str) -> Option<Self> {
+        Some(match word_and_number(text)? {
+            ("select_node", Some(n)) => Self::SelectNode(n),
+            ("open_move_menu", _) => Self::OpenMoveMenu,
+            ("open_construct_menu", _) => Self::OpenConstructMenu,
+            ("stockpile_in", Some(n)) => Self::StockpileInGroup(n),
+            ("building_in", Some(n)) => Self::BuildingInGroup(n),
+            ("turn", Some(n)) => Self::Turn(n as u32),
+            ("plan_move", _) => Self::PlanShipMove,
+            ("continue", _) => Self::Continue,
+            _ => return None,
+        })
+    }
+
+    fn reached(
+        &self,
+        events: &[&UiEvent],
+        map: &Map,
+        turns: &TurnCount,
+        ship: Option<&Ship>,
+    ) -> bool {
+        let in_group = |group: usize, stockpile: bool| {
+            map.groups
+                .get(&GroupId(group))
+                .into_iter()
+                .flatten()
+                .any(|node_id| match map.occupation.get(node_id) {
+                    Some(NodeOccupant::Stockpile { .. }) => stockpile,
+                    Some(NodeOccupant::Construction { .. }) => !stockpile,
+                    None => false,
+                })
+        };
+        match self {
+            Self::SelectNode(n) => events
+                .iter()
+                .any(|e| matches!(e, UiEvent::SelectNodeForConstruction(id) if id.0 == *n)),
+            Self::OpenMoveMenu => events
+                .iter()
+                .any(|e| matches!(e, UiEvent::SelectNodeForMove(_, _))),
+            Self::OpenConstructMenu => events
+                .iter()
+                .any(|e| matches!(e, UiEvent::ConstructOnNode(_))),
+            Self::StockpileInGroup(group) => in_group(*group, true),
+            Self::BuildingInGroup(group) => in_group(*group, false),
+            Self::Turn(turn) => turns.count >= *turn,
+            Self::PlanShipMove => ship.is_some_and(|ship| ship.planned_move.is_some()),
+            Self::Continue => false,
+        }
+    }
+}
+
+struct TutorialStep {
+    /// message id of the explanation
+    text: &'static str,
+    focus: TutorialFocus,
+    until: TutorialTrigger,
+}
+
+static TUTORIAL: OnceLock<Vec<TutorialStep>> = OnceLock::new();
+
+/// `message id = focus | trigger` lines, in the order they are shown
+fn parse_tutorial(source: &'static str) -> Vec<TutorialStep> {
+    source
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let step = line.split_once('=').and_then(|(text, value)| {
+                let (focus, until) = value.split_once('|')?;
+                Some(TutorialStep {
+                    text: text.trim(),
+                    focus: TutorialFocus::parse(focus)?,
+                    until: TutorialTrigger::parse(until)?,
+                })
+            });
+            if step.is_none() {
+                warn!("bad tutorial step: {}", line);
+            }
+            step
+        })
+        .collect()
+}
+
+fn tutorial_steps() -> &'static [TutorialStep] {
+    TUTORIAL.get_or_init(|| parse_tutorial(include_str!("../assets/tutorial.txt")))
+}
+
 #[derive(Component)]
-struct UiConstruct;
+struct UiTutorial(usize);
 
-fn ui_on_construction(
+#[derive(Component)]
+struct TutorialMarker;
+
+#[allow(clippy::too_many_arguments)]
+fn tutorial(
     mut commands: Commands,
     handles: Res<AssetHandles>,
-    query_ui_cons: Query<(Entity, &UiConstruct)>,
+    theme: Res<Theme>,
+    mut tutorial: ResMut<Tutorial>,
     mut event_ui: EventReader<UiEvent>,
+    query_marker: Query<Entity, With<TutorialMarker>>,
+    query_panel: Query<(Entity, &UiTutorial)>,
+    mut query_buttons: Query<(&UiButton, &mut BorderColor)>,
     map: Res<Map>,
+    turns: Res<TurnCount>,
+    ship_q: Query<&Ship>,
 ) {
-    if event_ui.is_empty() {
-        return;
-    }
-
-    for (e, _) in query_ui_cons.iter() {
+    for e in query_marker.iter() {
         commands.entity(e).despawn_recursive();
     }
-
-    let event = event_ui
-        .iter()
-        .find(|e| matches!(e, UiEvent::ConstructOnNode(_)));
-    let Some(UiEvent::ConstructOnNode(id)) = event else {
+    let events: Vec<&UiEvent> = event_ui.iter().collect();
+
+    let step = tutorial_steps()
+        .get(tutorial.step)
+        .filter(|_| tutorial.active);
+    let mut shown = false;
+    for (e, panel) in query_panel.iter() {
+        if step.is_some() && panel.0 == tutorial.step {
+            shown = true;
+        } else {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+    for (button, mut border) in query_buttons.iter_mut() {
+        if step.is_some_and(|step| step.focus.is_button(button)) {
+            *border = theme.focus.into();
+        } else if border.0 == theme.focus {
+            *border = theme.button_border.into();
+        }
+    }
+    let Some(step) = step else {
         return;
     };
 
-    let pos = map.positions.get(id).unwrap();
-    commands.spawn((
-        SpriteSheetBundle {
-            transform: Transform::default().with_translation(pos.extend(4.)),
-            sprite: TextureAtlasSprite {
-                index: 2,
+    if step
+        .until
+        .reached(&events, &map, &turns, ship_q.get_single().ok())
+    {
+        tutorial.step += 1;
+        return;
+    }
+
+    let marker = match step.focus {
+        TutorialFocus::Node(n) => map.positions.get(&NodeId(n)).map(|pos| (pos, 2)),
+        TutorialFocus::Planet(n) => map.group_positions.get(&GroupId(n)).map(|pos| (pos, 5)),
+        _ => None,
+    };
+    if let Some((pos, index)) = marker {
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform::default().with_translation(pos.extend(5.)),
+                sprite: TextureAtlasSprite {
+                    color: theme.focus,
+                    index,
+                    ..Default::default()
+                },
+                texture_atlas: handles.atlas.clone(),
                 ..Default::default()
             },
-            texture_atlas: handles.atlas.clone(),
-            ..Default::default()
-        },
-        Selected,
-    ));
+            TutorialMarker,
+            InGame,
+        ));
+    }
 
-    let big_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::WHITE,
-    };
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 20.0,
-        color: Color::WHITE,
-    };
-    let small_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 14.0,
-        color: Color::WHITE,
-    };
+    if shown {
+        return;
+    }
+
+    let text_style = theme.normal(&handles);
     commands
         .spawn((
             NodeBundle {
                 style: Style {
                     position_type: PositionType::Absolute,
                     bottom: Val::Percent(0.),
-                    right: Val::Percent(0.),
-                    width: Val::Percent(25.),
-                    height: Val::Percent(100.),
+                    left: Val::Percent(20.),
+                    width: Val::Percent(50.),
                     border: UiRect::all(Val::Px(5.0)),
+                    padding: UiRect::all(Val::Px(5.0)),
                     flex_direction: FlexDirection::Column,
                     ..default()
                 },
-                background_color: Color::rgb(0.1, 0.1, 0.1).into(),
-                border_color: Color::WHITE.into(),
+                background_color: theme.panel.into(),
+                border_color: theme.focus.into(),
                 ..default()
             },
-            UiConstruct,
+            UiTutorial(tutorial.step),
+            InGame,
         ))
         .with_children(|root| {
-            root.spawn(
-                TextBundle::from_section("Construct", big_text_style.clone()).with_style(Style {
-                    position_type: PositionType::Relative,
-                    top: Val::Percent(0.),
-                    right: Val::Percent(0.),
-                    margin: UiRect::bottom(Val::Px(10.)),
+            root.spawn(TextBundle::from_section(tr(step.text), text_style.clone()));
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
                     ..default()
-                }),
-            );
-            for constr in ConstructionVariant::iter() {
-                let group_id = map.group_from_node(id);
-                let cash = *map
-                    .get_group_bunch(&group_id)
-                    .res
-                    .get(&ResourceVariant::Material)
-                    .clone()
-                    .unwrap_or(&0);
-                let can_buy = cash >= constr.get_material_cost();
-                root.spawn((
-                    ButtonBundle {
-                        style: Style {
-                            flex_direction: FlexDirection::Row,
-                            border: UiRect::all(Val::Px(3.0)),
-                            margin: UiRect::all(Val::Px(2.)),
-                            ..Default::default()
-                        },
-                        background_color: Color::rgb(0.14, 0.14, 0.14).into(),
-                        border_color: Color::rgb(0.2, 0.2, 0.2).into(),
-                        ..Default::default()
-                    },
-                    UiButton::Construct(id.clone(), constr.clone()),
-                ))
-                .with_children(|constr_node| {
-                    constr_node.spawn(AtlasImageBundle {
-                        style: Style {
-                            width: Val::Px(64.),
-                            height: Val::Px(64.),
-                            ..Default::default()
-                        },
-                        texture_atlas: handles.atlas.clone(),
-                        texture_atlas_image: UiTextureAtlasImage {
-                            index: constr.get_sprite_index(),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    });
-                    constr_node
-                        .spawn(NodeBundle {
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                let mut buttons = vec![(UiButton::TutorialSkip, tr("tutorial.skip"))];
+                if matches!(step.until, TutorialTrigger::Continue) {
+                    buttons.push((UiButton::TutorialNext, tr("tutorial.continue")));
+                }
+                for (button, label) in buttons {
+                    row.spawn((
+                        ButtonBundle {
                             style: Style {
-                                flex_direction: FlexDirection::Column,
+                                border: UiRect::all(Val::Px(3.0)),
                                 margin: UiRect::all(Val::Px(2.)),
                                 ..Default::default()
                             },
+                            background_color: theme.button.into(),
+                            border_color: theme.button_border.into(),
                             ..Default::default()
-                        })
-                        .with_children(|details| {
-                            details.spawn(
-                                TextBundle::from_section(constr.to_string(), text_style.clone())
-                                    .with_style(Style {
-                                        position_type: PositionType::Relative,
-                                        ..default()
-                                    }),
-                            );
-                            details.spawn(
-                                TextBundle::from_section(
-                                    format!(
-                                        "Costs: {} {}, you have {} {} in this sector",
-                                        constr.get_material_cost(),
-                                        ResourceVariant::Material.to_string(),
-                                        cash,
-                                        ResourceVariant::Material.to_string()
-                                    ),
-                                    if can_buy {
-                                        small_text_style.clone()
-                                    } else {
-                                        TextStyle {
-                                            font: handles.font.clone(),
-                                            font_size: 14.0,
-                                            color: Color::RED,
-                                        }
-                                    },
-                                )
-                                .with_style(Style {
-                                    position_type: PositionType::Relative,
-                                    ..default()
-                                }),
-                            );
-                            // assuming one bunch
-                            let prod = constr.produce_resources();
-                            let cons = constr.request_resources();
-                            let (mut pvar, mut pamt) = (ResourceVariant::Power, 0);
-                            for (var, amt) in prod.res.iter() {
-                                pvar = var.clone();
-                                pamt = amt.clone();
-                            }
-                            let (mut cvar, mut camt) = (ResourceVariant::Power, 0);
-                            for (var, amt) in cons.res.iter() {
-                                cvar = var.clone();
-                                camt = amt.clone();
-                            }
-                            let cooldown = constr.get_cooldown();
-                            details.spawn(
-                                TextBundle::from_section(
-                                    format!(
-                                        "Generates: {} {} using {} {} every {} turns",
-                                        pamt,
-                                        pvar.to_string(),
-                                        camt,
-                                        cvar.to_string(),
-                                        cooldown
-                                    ),
-                                    small_text_style.clone(),
-                                )
-                                .with_style(Style {
-                                    position_type: PositionType::Relative,
-                                    ..default()
-                                }),
-                            );
-                        });
-                });
-            }
-        });
-}
-
-#[derive(Component)]
-struct UiTurnCount;
-
-fn ui_topleft(turns: Res<TurnCount>, mut query: Query<(&UiTurnCount, &mut Text)>) {
-    if let Ok((_, mut text)) = query.get_single_mut() {
-        text.sections[0].value = format!("Turn {}", turns.count);
-    }
-}
-
-fn setup_ui_topleft(mut commands: Commands, handles: Res<AssetHandles>, turns: Res<TurnCount>) {
-    let big_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::WHITE,
-    };
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 20.0,
-        color: Color::WHITE,
-    };
-    let small_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 13.0,
-        color: Color::WHITE,
-    };
-    commands
-        .spawn((NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                top: Val::Percent(0.),
-                left: Val::Percent(0.),
-                width: Val::Percent(15.),
-                border: UiRect::all(Val::Px(5.0)),
-                flex_direction: FlexDirection::Column,
-                ..default()
-            },
-            background_color: Color::rgb(0.1, 0.1, 0.1).into(),
-            border_color: Color::WHITE.into(),
-            ..default()
-        },))
-        .with_children(|root| {
-            root.spawn((
-                ButtonBundle {
-                    style: Style {
-                        flex_direction: FlexDirection::Column,
-                        margin: UiRect::all(Val::Px(2.)),
-                        ..Default::default()
-                    },
-                    background_color: Color::rgb(0.14, 0.14, 0.14).into(),
-                    ..Default::default()
-                },
-                UiButton::EndTurn,
-            ))
-            .with_children(|details| {
-                details.spawn(
-                    TextBundle::from_section("End Turn", big_text_style.clone()).with_style(
-                        Style {
-                            position_type: PositionType::Relative,
-                            ..default()
                         },
-                    ),
-                );
-                details.spawn((
-                    TextBundle::from_section(format!("Turn {}", turns.count), text_style.clone())
-                        .with_style(Style {
-                            position_type: PositionType::Relative,
-                            ..default()
-                        }),
-                    UiTurnCount,
-                ));
-                details.spawn(TextBundle::from_section(
-                    "Hotkey: Delete",
-                    text_style.clone(),
-                ));
-                details.spawn(
-                    TextBundle::from_section(
-                        "Each turn you eat 1 Food from the ship inventory.",
-                        small_text_style.clone(),
-                    )
-                    .with_style(Style {
-                        position_type: PositionType::Relative,
-                        margin: UiRect::all(Val::Px(10.)),
-                        ..default()
-                    }),
-                );
-                details.spawn(
-                    TextBundle::from_section(
-                        "With 100 Fusion Fuel and 100 Food in the ship you will be able to leave\
-                        this system.",
-                        small_text_style.clone(),
-                    )
-                    .with_style(Style {
-                        position_type: PositionType::Relative,
-                        margin: UiRect::all(Val::Px(10.)),
-                        ..default()
-                    }),
-                );
+                        button,
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(label, text_style.clone()));
+                    });
+                }
             });
         });
 }
@@ -2551,54 +8680,121 @@ struct UiGameOver;
 
 fn gameover_reset(mut next_state: ResMut<NextState<AppState>>, keys: Res<Input<KeyCode>>) {
     if keys.just_pressed(KeyCode::Space) {
-        next_state.set(AppState::Gameplay);
+        next_state.set(AppState::MainMenu);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn ui_win(
     mut commands: Commands,
     handles: Res<AssetHandles>,
+    theme: Res<Theme>,
     query_ui: Query<(Entity, &UiGameOver)>,
+    map: Res<Map>,
+    turns: Res<TurnCount>,
+    stats: Res<Stats>,
+    mut leaderboard: ResMut<Leaderboard>,
+    campaign: Res<Campaign>,
 ) {
     for (e, _) in query_ui.iter() {
         commands.entity(e).despawn_recursive();
     }
-    let big_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::WHITE,
-    };
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 20.0,
-        color: Color::WHITE,
-    };
-    let small_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 10.0,
-        color: Color::WHITE,
+    let score = Score::new(&map, turns.count);
+    let rank = leaderboard.submit(&map.name, score.total());
+    leaderboard.save();
+    let best = leaderboard.top(&map.name);
+    let produced: Bunch = stats
+        .turns
+        .iter()
+        .flat_map(|turn| turn.produced.values().cloned())
+        .sum();
+    let produced = Bunch {
+        res: produced
+            .res
+            .into_iter()
+            .filter(|(var, _)| var.is_stockpiled())
+            .collect(),
     };
+    let built: u32 = stats.built.values().sum();
+    let summary = [
+        trf(
+            "win.score",
+            &[
+                &score.total(),
+                &score.speed,
+                &score.surplus,
+                &score.buildings,
+            ],
+        ),
+        match rank {
+            Some(rank) => trf("win.rank", &[&rank, &best.len(), &map.name]),
+            None => trf("win.unranked", &[&best.len(), &map.name]),
+        },
+        trf("win.turns", &[&turns.count]),
+        trf("win.built", &[&built]),
+        trf("win.produced", &[&produced.describe()]),
+    ];
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
+    let small_text_style = theme.small(&handles);
     commands
-        .spawn((NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                width: Val::Percent(100.),
-                height: Val::Percent(100.),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                flex_direction: FlexDirection::Column,
-                border: UiRect::all(Val::Px(5.0)),
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    border: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                border_color: theme.positive.into(),
                 ..default()
             },
-            background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
-            border_color: Color::GREEN.into(),
-            ..default()
-        },))
+            InGame,
+        ))
         .with_children(|root| {
+            root.spawn(TextBundle::from_section(
+                tr("win.title"),
+                TextStyle {
+                    color: theme.positive,
+                    ..big_text_style.clone()
+                },
+            ));
+            root.spawn(
+                TextBundle::from_section(tr("win.text"), big_text_style.clone()).with_style(
+                    Style {
+                        position_type: PositionType::Relative,
+                        margin: UiRect::bottom(Val::Px(20.)),
+                        ..default()
+                    },
+                ),
+            );
+            for line in summary {
+                root.spawn(TextBundle::from_section(line, text_style.clone()));
+            }
+            root.spawn(TextBundle::from_section(
+                trf(
+                    "win.best",
+                    &[&best
+                        .iter()
+                        .map(|score| score.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")],
+                ),
+                small_text_style.clone(),
+            ));
             root.spawn(
                 TextBundle::from_section(
-                    "You have enough fusion fuel and food to continue your journey! Godspeed!",
-                    big_text_style.clone(),
+                    tr(if campaign.current.is_some() {
+                        "end.starmap"
+                    } else {
+                        "end.back"
+                    }),
+                    text_style.clone(),
                 )
                 .with_style(Style {
                     position_type: PositionType::Relative,
@@ -2608,59 +8804,1061 @@ fn ui_win(
         });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn ui_gameover(
     mut commands: Commands,
     handles: Res<AssetHandles>,
+    theme: Res<Theme>,
     query_ui: Query<(Entity, &UiGameOver)>,
+    map: Res<Map>,
+    turns: Res<TurnCount>,
+    crew: Res<Crew>,
+    scenario: Res<Scenario>,
 ) {
     for (e, _) in query_ui.iter() {
         commands.entity(e).despawn_recursive();
     }
-    let big_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 30.0,
-        color: Color::WHITE,
-    };
-    let text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 20.0,
-        color: Color::WHITE,
-    };
-    let small_text_style = TextStyle {
-        font: handles.font.clone(),
-        font_size: 10.0,
-        color: Color::WHITE,
-    };
+    let failed: Vec<String> = scenario
+        .objectives
+        .iter()
+        .filter(|o| scenario.status(o, turns.count, &map, &crew) == Status::Failed)
+        .map(|o| o.describe(&map))
+        .collect();
+    let big_text_style = theme.big(&handles);
+    let text_style = theme.normal(&handles);
     commands
-        .spawn((NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                width: Val::Percent(100.),
-                height: Val::Percent(100.),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                flex_direction: FlexDirection::Column,
-                border: UiRect::all(Val::Px(5.0)),
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    border: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                border_color: theme.negative.into(),
                 ..default()
             },
-            background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
-            border_color: Color::RED.into(),
-            ..default()
-        },))
+            InGame,
+        ))
         .with_children(|root| {
+            root.spawn(TextBundle::from_section(
+                tr("lose.title"),
+                TextStyle {
+                    color: theme.negative,
+                    ..big_text_style.clone()
+                },
+            ));
             root.spawn(
-                TextBundle::from_section("You lose! You ran out of food.", big_text_style.clone())
-                    .with_style(Style {
+                TextBundle::from_section(tr("lose.text"), big_text_style.clone()).with_style(
+                    Style {
                         position_type: PositionType::Relative,
+                        margin: UiRect::bottom(Val::Px(10.)),
                         ..default()
-                    }),
+                    },
+                ),
             );
+            for reason in failed {
+                root.spawn(TextBundle::from_section(reason, text_style.clone()));
+            }
             root.spawn(
-                TextBundle::from_section("Reload the page to retry :)", text_style.clone())
-                    .with_style(Style {
-                        position_type: PositionType::Relative,
-                        ..default()
-                    }),
+                TextBundle::from_section(tr("end.back"), text_style.clone()).with_style(Style {
+                    position_type: PositionType::Relative,
+                    ..default()
+                }),
+            );
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASES: u64 = 500;
+
+    /// small xorshift so the cases are reproducible from the seed
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn range(&mut self, lo: u32, hi: u32) -> u32 {
+            lo + (self.next() % (hi - lo + 1) as u64) as u32
+        }
+
+        fn pick<T: Clone>(&mut self, items: &[T]) -> T {
+            items[self.next() as usize % items.len()].clone()
+        }
+    }
+
+    fn random_map(rng: &mut Rng) -> Map {
+        let resources: Vec<ResourceVariant> = ResourceVariant::iter().collect();
+        let constructions: Vec<ConstructionVariant> = ConstructionVariant::iter().collect();
+        let mut map = Map {
+            name: "random".to_string(),
+            brightness: 1.,
+            groups: HashMap::new(),
+            edges: vec![],
+            positions: HashMap::new(),
+            group_positions: HashMap::new(),
+            occupation: HashMap::new(),
+            fired: vec![],
+            lanes: vec![],
+            orbits: HashMap::new(),
+            turn: 0,
+            kinds: HashMap::new(),
+            scouted: vec![],
+            deposits: HashMap::new(),
+        };
+        let mut next_node = 0;
+        for group in 0..rng.range(1, 4) as usize {
+            let mut nodes = vec![];
+            for _ in 0..rng.range(1, 9) {
+                let id = NodeId(next_node);
+                next_node += 1;
+                match rng.range(0, 3) {
+                    0 => {}
+                    1 => map.set_at(
+                        &id,
+                        NodeOccupant::Construction {
+                            var: rng.pick(&constructions),
+                            cooldown: 0,
+                            tier: 1,
+                        },
+                    ),
+                    _ => map.set_at(
+                        &id,
+                        NodeOccupant::Stockpile {
+                            var: rng.pick(&resources),
+                            amt: rng.range(1, MAX_STOCKPILE),
+                        },
+                    ),
+                }
+                nodes.push(id);
+            }
+            map.groups.insert(GroupId(group), nodes);
+            map.scouted.push(GroupId(group));
+        }
+        map
+    }
+
+    fn total(map: &Map, group: &GroupId, var: &ResourceVariant) -> u32 {
+        *map.get_group_bunch(group)
+            .unwrap()
+            .res
+            .get(var)
+            .unwrap_or(&0)
+    }
+
+    /// room left for the resource in the group
+    fn capacity(map: &Map, group: &GroupId, var: &ResourceVariant) -> u32 {
+        map.groups[group]
+            .iter()
+            .map(|id| match map.occupation.get(id) {
+                None => MAX_STOCKPILE,
+                Some(NodeOccupant::Stockpile { var: v, amt }) if v == var => MAX_STOCKPILE - amt,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn stockpiles(map: &Map) -> Vec<(usize, ResourceVariant, u32)> {
+        let mut piles: Vec<_> = map
+            .occupation
+            .iter()
+            .filter_map(|(id, occ)| match occ {
+                NodeOccupant::Stockpile { var, amt } => Some((id.0, var.clone(), *amt)),
+                _ => None,
+            })
+            .collect();
+        piles.sort_by_key(|pile| pile.0);
+        piles
+    }
+
+    fn check_piles(map: &Map, seed: u64) {
+        for (id, _, amt) in stockpiles(map) {
+            assert!(amt > 0, "seed {}: empty stockpile left at {}", seed, id);
+            assert!(
+                amt <= MAX_STOCKPILE,
+                "seed {}: stockpile at {} holds {}",
+                seed,
+                id,
+                amt
+            );
+        }
+    }
+
+    fn total_everywhere(map: &Map, var: &ResourceVariant) -> u32 {
+        map.groups.keys().map(|group| total(map, group, var)).sum()
+    }
+
+    #[test]
+    fn edges_follow_open_lanes() {
+        let mut map = Map::test();
+        map.dock(&GroupId(0), &GroupId(3));
+        for turn in 0..200 {
+            map.update_orbits(turn);
+            for (a, b) in map.lanes.clone() {
+                assert_eq!(
+                    map.edges.contains(&(a.clone(), b.clone())),
+                    map.lane_open_at(&a, &b, turn),
+                    "turn {}",
+                    turn
+                );
+                assert!(map.jump_cost(&a, &b) >= 1);
+            }
+            assert_eq!(map.star(&GroupId(0)), vec![GroupId(3)], "turn {}", turn);
+        }
+    }
+
+    #[test]
+    fn positions_follow_orbits() {
+        let mut map = Map::test();
+        let offset = |map: &Map, id: usize, group: usize| {
+            map.positions[&NodeId(id)] - map.group_positions[&GroupId(group)]
+        };
+        let before = offset(&map, 16, 4);
+        let ship = map.positions[&NodeId(0)];
+        let start = map.group_positions[&GroupId(4)];
+        for turn in 1..40 {
+            map.update_orbits(turn);
+            for (group_id, orbit) in map.orbits.iter() {
+                assert_eq!(
+                    map.group_positions[group_id],
+                    orbit.screen_position(turn),
+                    "turn {}",
+                    turn
+                );
+            }
+            assert!((offset(&map, 16, 4) - before).length() < 1e-3);
+            assert_eq!(map.positions[&NodeId(0)], ship);
+        }
+        assert_ne!(map.group_positions[&GroupId(4)], start);
+        // a jump costs what the bodies are apart now
+        let (a, b) = (GroupId(2), GroupId(7));
+        let au = (map.group_positions[&a] - SUN) / AU_SCREEN
+            - (map.group_positions[&b] - SUN) / AU_SCREEN;
+        assert_eq!(
+            map.jump_cost(&a, &b),
+            ((au.length() / JUMP_AU).ceil() as u32).max(1)
+        );
+    }
+
+    #[test]
+    fn orbits_stay_on_screen_and_off_the_hold() {
+        // the window is 1200x720 and the hold is drawn up to y -129
+        let screen = Rect::new(-600., -129., 600., 360.);
+        let mut map = Map::test();
+        for turn in 0..200 {
+            map.update_orbits(turn);
+            for (group_id, nodes) in map.groups.iter() {
+                if *group_id == GroupId(0) {
+                    continue;
+                }
+                let planet = map.group_positions[group_id];
+                for pos in nodes.iter().map(|id| map.positions[id]).chain([planet]) {
+                    let rect = Rect::from_center_size(pos, TILE_SIZE);
+                    assert_eq!(
+                        rect.intersect(screen),
+                        rect,
+                        "group {} at turn {}",
+                        group_id.0,
+                        turn
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shuttles_conserve_cargo() {
+        let fuel = ResourceVariant::RocketFuel;
+        for seed in 1..=CASES {
+            let mut rng = Rng(seed);
+            let mut map = random_map(&mut rng);
+            let groups = map.groups.len();
+            if groups < 3 {
+                continue;
+            }
+            for group in 1..groups - 1 {
+                map.edges.push((GroupId(group), GroupId(group + 1)));
+            }
+            let from = GroupId(rng.range(1, groups as u32 - 1) as usize);
+            let to = GroupId(rng.range(1, groups as u32 - 1) as usize);
+            let node = rng.pick(&map.groups[&from]);
+            let mut shuttles = Shuttles::default();
+            let piles = stockpiles(&map);
+            let Some(NodeOccupant::Stockpile { var, .. }) = map.occupation.get(&node).cloned()
+            else {
+                let err = shuttles.launch(&mut map, &node, &to);
+                assert_eq!(err.unwrap_err(), MapError::NoStockpile(node.clone()));
+                continue;
+            };
+            let before = total_everywhere(&map, &var);
+            let fuel_before = total_everywhere(&map, &fuel);
+            let Ok(_) = shuttles.launch(&mut map, &node, &to) else {
+                assert_eq!(stockpiles(&map), piles, "seed {}: failed launch", seed);
+                continue;
+            };
+            let (cost, _) = Shuttles::plan(&map, &from, &to).unwrap();
+            let expected = if var == fuel { before - cost } else { before };
+            if var != fuel {
+                assert_eq!(
+                    total_everywhere(&map, &fuel),
+                    fuel_before - cost,
+                    "seed {}",
+                    seed
+                );
+            }
+            for _ in 0..=SHUTTLE_RANGE {
+                let flying: u32 = shuttles.flights.iter().map(|flight| flight.amt).sum();
+                assert_eq!(
+                    total_everywhere(&map, &var) + flying,
+                    expected,
+                    "seed {}",
+                    seed
+                );
+                shuttles.fly(&mut map).unwrap();
+                check_piles(&map, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn add_conserves_resources() {
+        for seed in 1..=CASES {
+            let mut rng = Rng(seed);
+            let mut map = random_map(&mut rng);
+            let group = GroupId(rng.range(0, map.groups.len() as u32 - 1) as usize);
+            let var = rng.pick(&ResourceVariant::iter().collect::<Vec<_>>());
+            let amt = rng.range(0, 3 * MAX_STOCKPILE);
+            let before = total(&map, &group, &var);
+            let room = capacity(&map, &group, &var);
+
+            let (actions, overflow) = map.add_resource_in_group(&group, &var, amt).unwrap();
+
+            let stored: i32 = actions.iter().map(|(_, _, diff)| diff).sum();
+            assert_eq!(stored as u32 + overflow, amt, "seed {}", seed);
+            assert_eq!(
+                total(&map, &group, &var),
+                before + stored as u32,
+                "seed {}",
+                seed
             );
+            assert_eq!(overflow, amt.saturating_sub(room), "seed {}", seed);
+            check_piles(&map, seed);
+        }
+    }
+
+    #[test]
+    fn take_conserves_resources() {
+        for seed in 1..=CASES {
+            let mut rng = Rng(seed);
+            let mut map = random_map(&mut rng);
+            let group = GroupId(rng.range(0, map.groups.len() as u32 - 1) as usize);
+            let var = rng.pick(&ResourceVariant::iter().collect::<Vec<_>>());
+            let before = total(&map, &group, &var);
+            let amt = rng.range(0, before + 10);
+            let piles_before = stockpiles(&map);
+
+            match map.take_resource_in_group(&group, &var, amt) {
+                Ok(actions) => {
+                    let taken: i32 = actions.iter().map(|(_, _, diff)| diff).sum();
+                    assert_eq!(taken as u32, amt, "seed {}", seed);
+                    assert_eq!(total(&map, &group, &var), before - amt, "seed {}", seed);
+                    for (id, abs, _) in actions {
+                        match map.occupation.get(&id) {
+                            Some(NodeOccupant::Stockpile { amt, .. }) => {
+                                assert_eq!(*amt, abs, "seed {}", seed)
+                            }
+                            None => assert_eq!(abs, 0, "seed {}", seed),
+                            _ => panic!("seed {}: stockpile replaced at {:?}", seed, id),
+                        }
+                    }
+                }
+                Err(err) => {
+                    assert!(amt > before, "seed {}: {}", seed, err);
+                    assert_eq!(err, MapError::InsufficientResources(var), "seed {}", seed);
+                    assert_eq!(stockpiles(&map), piles_before, "seed {}", seed);
+                }
+            }
+            check_piles(&map, seed);
+        }
+    }
+
+    #[test]
+    fn add_then_take_round_trips() {
+        for seed in 1..=CASES {
+            let mut rng = Rng(seed);
+            let mut map = random_map(&mut rng);
+            let group = GroupId(rng.range(0, map.groups.len() as u32 - 1) as usize);
+            let var = rng.pick(&ResourceVariant::iter().collect::<Vec<_>>());
+            let amt = rng.range(0, 2 * MAX_STOCKPILE);
+            let before = total(&map, &group, &var);
+
+            let (_, overflow) = map.add_resource_in_group(&group, &var, amt).unwrap();
+            map.take_resource_in_group(&group, &var, amt - overflow)
+                .unwrap();
+
+            assert_eq!(total(&map, &group, &var), before, "seed {}", seed);
+            check_piles(&map, seed);
+        }
+    }
+
+    #[test]
+    fn solar_output_follows_distance() {
+        let mut map = Map::test();
+        let solar = ConstructionVariant::SolarField;
+        let power = |map: &Map, group: usize| {
+            map.output(&GroupId(group), &solar, 1).res[&ResourceVariant::Power]
+        };
+        // the design numbers hold in the belt
+        assert_eq!(power(&map, 1), 3);
+        assert_eq!(power(&map, 0), 5);
+        assert!(power(&map, 2) > power(&map, 1));
+        assert!(power(&map, 7) < power(&map, 1));
+        map.dock(&GroupId(0), &GroupId(2));
+        assert!(power(&map, 0) > power(&map, 2));
+        assert_eq!(
+            map.output(&GroupId(7), &ConstructionVariant::Smelter, 1),
+            ConstructionVariant::Smelter.produce_at(1)
+        );
+    }
+
+    #[test]
+    fn deposits_run_dry() {
+        let mut map = Map::test();
+        let group = GroupId(1);
+        let ore = ResourceVariant::Ore;
+        let initial = map.reserves(&group).res[&ore];
+        let mut extracted = 0;
+        let mut last = u32::MAX;
+        while map.can_extract(&group, &ConstructionVariant::Quarry) {
+            let got = *map
+                .extract(&group, &ConstructionVariant::Quarry, 1)
+                .res
+                .get(&ore)
+                .unwrap_or(&0);
+            assert!(got <= last, "output grew from {} to {}", last, got);
+            last = got;
+            extracted += got;
+        }
+        assert_eq!(extracted, initial);
+        assert!(map.reserves(&group).res.is_empty());
+        assert!(map
+            .extract(&group, &ConstructionVariant::Quarry, 1)
+            .res
+            .is_empty());
+        assert!(map.can_extract(&group, &ConstructionVariant::Smelter));
+    }
+
+    #[test]
+    fn probes_reveal_planets_for_fuel() {
+        let mut map = Map::test();
+        assert!(!map.is_scouted(&GroupId(2)));
+        assert_eq!(
+            buy_construction(&mut map, &NodeId(8), &ConstructionVariant::SolarField).unwrap_err(),
+            MapError::Unexplored(GroupId(2))
+        );
+        assert_eq!(
+            launch_probe(&mut map, &GroupId(1), &GroupId(2)).unwrap_err(),
+            MapError::InsufficientResources(ResourceVariant::RocketFuel)
+        );
+        assert!(!map.is_scouted(&GroupId(2)));
+        map.add_resource_in_group(&GroupId(0), &ResourceVariant::RocketFuel, 50)
+            .unwrap();
+        let jumps = map.distance(&GroupId(1), &GroupId(2)).unwrap();
+        let paid = launch_probe(&mut map, &GroupId(1), &GroupId(2)).unwrap();
+        assert!(map.is_scouted(&GroupId(2)));
+        let fuel = jumps * PROBE_FUEL_PER_JUMP;
+        assert_eq!(
+            total(&map, &GroupId(0), &ResourceVariant::RocketFuel),
+            50 - fuel
+        );
+        assert_eq!(
+            paid.iter()
+                .map(|act| match act {
+                    AutoAction::ConsumeResource { taken, .. } => *taken,
+                    _ => 0,
+                })
+                .sum::<u32>(),
+            fuel
+        );
+        // a second probe would be wasted
+        assert_eq!(
+            launch_probe(&mut map, &GroupId(1), &GroupId(2)).unwrap_err(),
+            MapError::Scouted(GroupId(2))
+        );
+        assert_eq!(
+            total(&map, &GroupId(0), &ResourceVariant::RocketFuel),
+            50 - fuel
+        );
+        // nothing links to a group cut off from the lanes
+        map.lanes
+            .retain(|(a, b)| *a != GroupId(7) && *b != GroupId(7));
+        map.update_orbits(map.turn);
+        assert_eq!(
+            launch_probe(&mut map, &GroupId(1), &GroupId(7)).unwrap_err(),
+            MapError::OutOfRange(GroupId(7))
+        );
+        assert!(!map.is_scouted(&GroupId(7)));
+        assert_eq!(
+            total(&map, &GroupId(0), &ResourceVariant::RocketFuel),
+            50 - fuel
+        );
+    }
+
+    #[test]
+    fn encounters_deal_with_the_ship_only() {
+        let mut map = Map::test();
+        let hold = GroupId(0);
+        map.add_resource_in_group(&hold, &ResourceVariant::Material, 10)
+            .unwrap();
+        map.add_resource_in_group(&hold, &ResourceVariant::RocketFuel, 20)
+            .unwrap();
+        let mut encounters = Encounters::new(3);
+        // materials for food and a wreck, both at the first asteroid
+        encounters.active = vec![
+            Encounter {
+                id: 0,
+                group: GroupId(1),
+                kind: EncounterKind::Trader(vec![1]),
+                turns_left: 2,
+            },
+            Encounter {
+                id: 1,
+                group: GroupId(1),
+                kind: EncounterKind::Wreck(20),
+                turns_left: 2,
+            },
+        ];
+        assert_eq!(
+            encounters.trade(&mut map, &GroupId(2), 0, 1).unwrap_err(),
+            MapError::Away(GroupId(1))
+        );
+        encounters.trade(&mut map, &GroupId(1), 0, 1).unwrap();
+        assert_eq!(total(&map, &hold, &ResourceVariant::Material), 0);
+        assert_eq!(total(&map, &hold, &ResourceVariant::Food), 8);
+        // every offer is good once, an empty trader leaves
+        assert_eq!(
+            encounters.trade(&mut map, &GroupId(1), 0, 1).unwrap_err(),
+            MapError::Gone
+        );
+        encounters.salvage(&mut map, &GroupId(1), 1).unwrap();
+        assert_eq!(total(&map, &hold, &ResourceVariant::Material), 20);
+        assert_eq!(
+            total(&map, &hold, &ResourceVariant::RocketFuel),
+            20 - SALVAGE_FUEL
+        );
+        assert!(encounters.active.is_empty());
+        // nobody stays longer than promised
+        for _ in 0..CASES {
+            encounters.tick(&map);
+            assert!(encounters
+                .active
+                .iter()
+                .all(|e| e.turns_left <= ENCOUNTER_TURNS.1 && e.group != hold));
+        }
+        assert!(encounters.next_id > 2);
+    }
+
+    #[test]
+    fn encounters_stay_out_of_the_fog() {
+        let mut map = Map::test();
+        let mut encounters = Encounters::new(11);
+        for _ in 0..CASES {
+            if let Some(encounter) = encounters.tick(&map) {
+                assert!(map.is_scouted(&encounter.group));
+            }
+            assert!(encounters.active.iter().all(|e| e.group == GroupId(1)));
+        }
+        // a probed planet can be visited too
+        map.scout(&GroupId(3));
+        assert!((0..CASES).any(|_| encounters
+            .tick(&map)
+            .is_some_and(|encounter| encounter.group == GroupId(3))));
+    }
+
+    #[test]
+    fn achievements_follow_the_run() {
+        let mut map = Map::test();
+        let mut stats = Stats::default();
+        assert!(!Goal::Win.reached(10, &map, &stats, false));
+        assert!(Goal::WinWithin(40).reached(40, &map, &stats, true));
+        assert!(!Goal::WinWithin(40).reached(41, &map, &stats, true));
+        let no_quarry = Goal::WinWithout(ConstructionVariant::Quarry);
+        assert!(no_quarry.reached(10, &map, &stats, true));
+        stats.built.insert(ConstructionVariant::Quarry, 1);
+        assert!(!no_quarry.reached(10, &map, &stats, true));
+        // lost power doesn't count as waste
+        stats.turns.push(TurnStats {
+            wasted: Bunch::single(ResourceVariant::Power, 5),
+            ..Default::default()
         });
+        assert!(Goal::WinWithoutWaste.reached(10, &map, &stats, true));
+        stats.turns.push(TurnStats {
+            wasted: Bunch::single(ResourceVariant::Ore, 5),
+            ..Default::default()
+        });
+        assert!(!Goal::WinWithoutWaste.reached(10, &map, &stats, true));
+        map.add_resource_in_group(&GroupId(0), &ResourceVariant::Food, 1)
+            .unwrap();
+        assert!(Goal::FoodLeft(1).reached(10, &map, &stats, false));
+
+        let mut profile = Profile::default();
+        assert!(profile.unlock("win"));
+        assert!(!profile.unlock("win"));
+        assert!(profile.unlock("swift"));
+        assert_eq!(
+            Profile::parse(&profile.to_text()).achievements,
+            ["win", "swift"]
+        );
+    }
+
+    #[test]
+    fn leaderboard_ranks_per_map() {
+        let mut board = Leaderboard::default();
+        assert_eq!(board.submit("test", 500), Some(1));
+        assert_eq!(board.submit("test", 700), Some(1));
+        assert_eq!(board.submit("test", 600), Some(2));
+        assert_eq!(board.submit("other", 100), Some(1));
+        assert_eq!(board.top("test"), vec![700, 600, 500]);
+        for _ in 0..LEADERBOARD_SIZE {
+            board.submit("test", 800);
+        }
+        assert_eq!(board.top("test").len(), LEADERBOARD_SIZE);
+        assert_eq!(board.submit("test", 10), None);
+        let board = Leaderboard::parse(&board.to_text());
+        assert_eq!(board.top("other"), vec![100]);
+        assert_eq!(board.top("test"), vec![800; LEADERBOARD_SIZE]);
+        // a file edited by hand is cut back to the best ones
+        let long = "test = 1\n".repeat(3 * LEADERBOARD_SIZE);
+        assert_eq!(Leaderboard::parse(&long).entries.len(), LEADERBOARD_SIZE);
+
+        // a faster win with the same map scores more
+        let map = Map::test();
+        assert!(Score::new(&map, 20).total() > Score::new(&map, 30).total());
+        assert_eq!(Score::new(&map, PAR_TURNS + 5).speed, 0);
+    }
+
+    #[test]
+    fn scenario_objectives_win_and_lose() {
+        let mut map = Map::test();
+        let crew = Crew::default();
+        let hold = GroupId(0);
+        let mut scenario = Scenario {
+            objectives: vec![
+                Objective::Stock(ResourceVariant::Food, 50),
+                Objective::Reach(GroupId(3), 10),
+                Objective::MinFood(5),
+                Objective::TurnLimit(20),
+            ],
+            start: Bunch::default(),
+            visited: vec![],
+        };
+        map.add_resource_in_group(&hold, &ResourceVariant::Food, 10)
+            .unwrap();
+        assert_eq!(scenario.outcome(1, &map, &crew), None);
+        // too late for planet 3
+        assert_eq!(scenario.outcome(11, &map, &crew), Some(false));
+        scenario.visit(&GroupId(3));
+        assert_eq!(scenario.outcome(11, &map, &crew), None);
+        map.take_resource_in_group(&hold, &ResourceVariant::Food, 6)
+            .unwrap();
+        assert_eq!(scenario.outcome(11, &map, &crew), Some(false));
+        // the ship needs more than asked
+        map.add_resource_in_group(&hold, &ResourceVariant::Food, 46)
+            .unwrap();
+        assert_eq!(scenario.outcome(11, &map, &crew), None);
+        map.add_resource_in_group(&hold, &ResourceVariant::Food, 1)
+            .unwrap();
+        assert_eq!(scenario.outcome(11, &map, &crew), Some(true));
+        // winning on the last turn still counts
+        assert_eq!(scenario.outcome(20, &map, &crew), Some(true));
+        scenario
+            .objectives
+            .push(Objective::Stock(ResourceVariant::Ore, 1));
+        assert_eq!(scenario.outcome(20, &map, &crew), Some(false));
+
+        let scenario = Scenario {
+            objectives: vec![Objective::Keep(ConstructionVariant::Lab, GroupId(2))],
+            start: Bunch::default(),
+            visited: vec![],
+        };
+        let lab = map.groups[&GroupId(2)][0].clone();
+        map.construct(&lab, &ConstructionVariant::Lab).unwrap();
+        assert_eq!(
+            scenario.status(&scenario.objectives[0], 1, &map, &crew),
+            Status::Done
+        );
+        map.occupation.remove(&lab);
+        assert_eq!(scenario.outcome(1, &map, &crew), Some(false));
+
+        let objectives = Scenario::test().objectives;
+        assert_eq!(objectives.len(), 3);
+        assert!(matches!(
+            objectives[0],
+            Objective::Stock(ResourceVariant::FusionFuel, 100)
+        ));
+        assert!(matches!(
+            Objective::parse("keep Lab 2"),
+            Some(Objective::Keep(ConstructionVariant::Lab, GroupId(2)))
+        ));
+        assert!(Objective::parse("reach 3").is_none());
+    }
+
+    #[test]
+    fn campaign_systems_link_and_carry_cargo() {
+        let systems = StarSystem::all();
+        for system in systems.iter() {
+            assert!(
+                system.objectives.iter().any(|o| o.is_goal()),
+                "{}",
+                system.id
+            );
+            assert!(system
+                .next
+                .iter()
+                .all(|id| systems.iter().any(|s| s.id == id)));
+            assert!(!system.start.res.is_empty());
+        }
+        let mut campaign = Campaign::default();
+        assert_eq!(campaign.reachable(&systems), vec![systems[0].id]);
+
+        let mut map = systems[0].map();
+        map.add_resource_in_group(&GroupId(0), &ResourceVariant::FusionFuel, 110)
+            .unwrap();
+        map.add_resource_in_group(&GroupId(0), &ResourceVariant::Ore, 10)
+            .unwrap();
+        campaign.current = Some(systems[0].id.to_string());
+        let mut research = Research::default();
+        research.pick(TechId("deep_mining"));
+        research.work(4);
+        campaign.win(&map, &research);
+        assert_eq!(
+            campaign.cargo,
+            Bunch::single(ResourceVariant::FusionFuel, 110)
+        );
+        let next = campaign.reachable(&systems);
+        assert_eq!(next.len(), systems[0].next.len());
+        assert!(!next.contains(&systems[0].id));
+
+        let loaded = Campaign::parse(&campaign.to_text());
+        assert_eq!(loaded.won, campaign.won);
+        assert_eq!(loaded.research.done, research.done);
+        assert_eq!(loaded.cargo, campaign.cargo);
+        let system = systems.iter().find(|s| s.id == next[0]).unwrap();
+        let start = system.scenario(&loaded.cargo).start;
+        assert_eq!(
+            start.res[&ResourceVariant::FusionFuel],
+            system.start.res[&ResourceVariant::FusionFuel] + 110
+        );
+    }
+
+    #[test]
+    fn skipped_animation_keeps_order_and_queued_turns() {
+        let consume = |i: usize| AutoAction::ConsumeResource {
+            from: NodeId(i),
+            to: NodeId(i + 1),
+            var: ResourceVariant::Food,
+            abs: i as u32,
+            diff: -1,
+            taken: 1,
+        };
+        let mut autoactions = AutoActions {
+            actions: (0..3).map(consume).collect(),
+            ..Default::default()
+        };
+        autoactions.start();
+        assert!(autoactions.timer.finished());
+        autoactions.current = Some(autoactions.actions.remove(0));
+
+        // an order during the animation doesn't jump a step
+        autoactions.timer.reset();
+        autoactions.actions.push(consume(3));
+        autoactions.start();
+        assert!(!autoactions.timer.finished());
+
+        let froms: Vec<NodeId> = autoactions
+            .skip()
+            .into_iter()
+            .filter_map(|act| match act {
+                AutoAction::ConsumeResource { from, .. } => Some(from),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(froms, (0..4).map(NodeId).collect::<Vec<_>>());
+        assert!(autoactions.done());
+
+        let mut speed = AnimSpeed::default();
+        let factors: Vec<Option<u32>> = (0..4)
+            .map(|_| {
+                let factor = speed.factor();
+                speed = speed.next();
+                factor
+            })
+            .collect();
+        assert_eq!(factors, vec![Some(1), Some(2), Some(4), None]);
+        assert_eq!(speed, AnimSpeed::Normal);
+    }
+
+    #[test]
+    fn upgrade_stops_at_max_tier() {
+        let mut map = random_map(&mut Rng(7));
+        let id = map.groups[&GroupId(0)][0].clone();
+        map.occupation.remove(&id);
+        assert_eq!(map.upgrade(&id), Err(MapError::NoConstruction(id.clone())));
+        map.construct(&id, &ConstructionVariant::SolarField)
+            .unwrap();
+        for tier in 2..=MAX_TIER {
+            assert_eq!(map.upgrade(&id), Ok(tier));
+        }
+        assert_eq!(map.upgrade(&id), Err(MapError::MaxTier(id.clone())));
+    }
+
+    #[test]
+    fn unknown_ids_are_errors() {
+        let mut map = random_map(&mut Rng(42));
+        let missing = GroupId(map.groups.len());
+        assert_eq!(
+            map.get_group_bunch(&missing),
+            Err(MapError::UnknownGroup(missing.clone()))
+        );
+        assert!(map
+            .add_resource_in_group(&missing, &ResourceVariant::Food, 1)
+            .is_err());
+        assert!(map
+            .take_resource_in_group(&missing, &ResourceVariant::Food, 1)
+            .is_err());
+        assert_eq!(
+            map.group_from_node(&NodeId(usize::MAX)),
+            Err(MapError::UnknownNode(NodeId(usize::MAX)))
+        );
+    }
+
+    #[test]
+    fn stats_count_what_buildings_took() {
+        let mut map = Map::test();
+        map.construct(&NodeId(5), &ConstructionVariant::AtmosphereHarvester)
+            .unwrap();
+        let actions = vec![
+            AutoAction::ConsumeResource {
+                from: NodeId(6),
+                to: NodeId(5),
+                var: ResourceVariant::Power,
+                abs: 0,
+                diff: 50,
+                taken: 30,
+            },
+            AutoAction::ProduceResource {
+                from: NodeId(5),
+                to: NodeId(7),
+                var: ResourceVariant::FusionFuel,
+                abs: 10,
+                diff: 10,
+            },
+        ];
+        let mut stats = Stats::default();
+        stats.record(3, &map, &actions, Bunch::default());
+        let turn = &stats.turns[0];
+        assert_eq!(
+            turn.consumed[&ConstructionVariant::AtmosphereHarvester],
+            Bunch::single(ResourceVariant::Power, 30)
+        );
+        assert_eq!(
+            turn.produced[&ConstructionVariant::AtmosphereHarvester],
+            Bunch::single(ResourceVariant::FusionFuel, 10)
+        );
+        assert!(stats
+            .to_csv()
+            .lines()
+            .any(|line| line.starts_with("3,consumed,")));
+    }
+
+    #[test]
+    fn crew_eats_starves_and_staffs_nearby() {
+        let mut crew = Crew {
+            awake: 2,
+            asleep: 1,
+        };
+        assert_eq!(crew.food_needed(), 2 * FOOD_PER_CREW);
+        crew.sleep();
+        assert_eq!(crew.food_needed(), FOOD_PER_CREW);
+        // the last one awake can't go to sleep
+        crew.sleep();
+        assert_eq!((crew.awake, crew.asleep), (1, 2));
+
+        assert!(crew.starve());
+        assert_eq!((crew.awake, crew.asleep), (1, 1));
+        crew.wake();
+        assert!(!crew.starve());
+        assert_eq!((crew.awake, crew.asleep), (1, 0));
+        assert!(!crew.starve());
+        assert_eq!(crew.awake, 0);
+
+        let quarry = ConstructionVariant::Quarry;
+        let ship_at = GroupId(1);
+        assert!(Crew::can_staff(2, &quarry, &GroupId(1), &ship_at));
+        assert!(Crew::can_staff(2, &quarry, &GroupId(0), &ship_at));
+        assert!(!Crew::can_staff(1, &quarry, &GroupId(1), &ship_at));
+        assert!(!Crew::can_staff(2, &quarry, &GroupId(3), &ship_at));
+        assert!(Crew::can_staff(
+            0,
+            &ConstructionVariant::SolarField,
+            &GroupId(3),
+            &ship_at
+        ));
+    }
+
+    #[test]
+    fn research_unlocks_and_discounts() {
+        let id = TechId;
+        assert_eq!(techs().len(), 6);
+        assert_eq!(tech(id("refining")).requires, vec![id("harvesting")]);
+
+        let mut research = Research::default();
+        assert!(research.unlocked(&ConstructionVariant::SolarField));
+        assert!(!research.unlocked(&ConstructionVariant::Quarry));
+        assert_eq!(research.work(5), None);
+
+        // locked techs can't be picked
+        research.pick(id("refining"));
+        assert_eq!(research.current, None);
+        research.pick(id("harvesting"));
+        assert_eq!(research.work(2), None);
+        // progress is kept when switching
+        research.pick(id("deep_mining"));
+        assert_eq!(research.work(4), Some(id("deep_mining")));
+        assert!(research.unlocked(&ConstructionVariant::Quarry));
+        research.pick(id("harvesting"));
+        assert_eq!(research.work(1), Some(id("harvesting")));
+        assert_eq!(research.current, None);
+
+        let harvester = ConstructionVariant::AtmosphereHarvester;
+        assert_eq!(research.cooldown(&harvester, 1), 3);
+        research.pick(id("fast_harvesting"));
+        research.work(5);
+        assert_eq!(research.cooldown(&harvester, 1), 2);
+        assert_eq!(research.cooldown(&harvester, MAX_TIER), 1);
+
+        let farm = ConstructionVariant::PlanetFarm;
+        assert_eq!(research.request(&farm), farm.request_resources());
+        research.pick(id("cheap_rockets"));
+        research.work(3);
+        assert_eq!(
+            research.request(&ConstructionVariant::ChemicalPlant),
+            Bunch::single(ResourceVariant::Material, 1)
+        );
+        research.pick(id("drip_irrigation"));
+        research.work(1);
+        research.work(2);
+        assert_eq!(
+            research.request(&farm),
+            Bunch::many(&[(ResourceVariant::Material, 8), (ResourceVariant::Water, 2)])
+        );
+
+        research.pick(id("refining"));
+        research.work(1);
+        let loaded = Research::parse(&research.to_text());
+        assert_eq!(loaded.done, research.done);
+        assert_eq!(loaded.current, Some(id("refining")));
+        assert_eq!(loaded.progress, research.progress);
+        assert!(Research::parse("tech = warp_drive").done.is_empty());
+    }
+
+    #[test]
+    fn power_goes_by_priority_and_charges_batteries() {
+        let mut map = Map::test();
+        let group = GroupId(4);
+        map.groups
+            .get_mut(&group)
+            .unwrap()
+            .extend((40..43).map(NodeId));
+        for (id, var) in [
+            (16, ConstructionVariant::PowerPlant),
+            (17, ConstructionVariant::Battery),
+            (18, ConstructionVariant::Refinery),
+            (19, ConstructionVariant::Smelter),
+            (40, ConstructionVariant::Refinery),
+        ] {
+            map.construct(&NodeId(id), &var).unwrap();
+        }
+        for (id, var) in [
+            (20, ResourceVariant::RocketFuel),
+            (21, ResourceVariant::Helium3),
+            (22, ResourceVariant::Helium4),
+            (23, ResourceVariant::Ore),
+        ] {
+            map.set_at(&NodeId(id), NodeOccupant::Stockpile { var, amt: 20 });
+        }
+        let mut research = Research::default();
+        let mut grid = PowerGrid::default();
+        let mut actions = vec![];
+        // ten power feed both refineries before the smelter
+        let (wasted, _) =
+            run_constructions(&mut map, &mut research, &mut grid, 10, &group, &mut actions);
+        let balance = &grid.last_turn[&group];
+        assert_eq!((balance.generated, balance.demand), (10, 12));
+        assert_eq!((balance.stored, balance.capacity), (0, BATTERY_CAPACITY));
+        assert_eq!(total(&map, &group, &ResourceVariant::Ore), 20);
+        assert_eq!(total(&map, &group, &ResourceVariant::FusionFuel), 10);
+        assert!(wasted.res.get(&ResourceVariant::Power).is_none());
+        assert!(actions.iter().any(|act| matches!(
+            act,
+            AutoAction::ProduceResource {
+                from: NodeId(16),
+                var: ResourceVariant::Power,
+                diff: 10,
+                ..
+            }
+        )));
+        let mut stats = Stats::default();
+        stats.record(1, &map, &actions, wasted);
+        assert_eq!(
+            stats.turns[0].produced[&ConstructionVariant::PowerPlant],
+            Bunch::single(ResourceVariant::Power, 10)
+        );
+        // the refineries cool down, the rest tops up the battery
+        for id in [16, 19] {
+            if let Some(NodeOccupant::Construction { cooldown, .. }) =
+                map.occupation.get_mut(&NodeId(id))
+            {
+                *cooldown = 0;
+            }
+        }
+        grid.stored.insert(group.clone(), 25);
+        let (wasted, _) =
+            run_constructions(&mut map, &mut research, &mut grid, 10, &group, &mut actions);
+        let balance = &grid.last_turn[&group];
+        assert_eq!((balance.generated, balance.demand), (10, 2));
+        assert_eq!(balance.stored, BATTERY_CAPACITY);
+        assert_eq!(grid.stored[&group], BATTERY_CAPACITY);
+        assert_eq!(wasted.res[&ResourceVariant::Power], 3);
+        assert_eq!(total(&map, &group, &ResourceVariant::Ore), 15);
+    }
+
+    #[test]
+    fn tutorial_steps_load_from_the_asset() {
+        let steps = tutorial_steps();
+        assert_eq!(steps.len(), 8);
+        let english = &strings()[&Language::English];
+        assert!(steps.iter().all(|step| english.contains_key(step.text)));
+        assert!(matches!(steps[0].focus, TutorialFocus::Node(2)));
+        assert!(matches!(steps[5].until, TutorialTrigger::Turn(2)));
+        assert!(matches!(
+            steps.last().unwrap().until,
+            TutorialTrigger::Continue
+        ));
+        assert!(TutorialTrigger::parse("select_node").is_none());
+        assert!(TutorialFocus::parse("orbit 3").is_none());
+    }
 }